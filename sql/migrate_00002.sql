@@ -0,0 +1 @@
+ALTER TABLE shows ADD COLUMN status VARCHAR NOT NULL DEFAULT 'active';
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS show_aliases (
+    show_id BIGINT NOT NULL REFERENCES shows (show_id),
+    alias VARCHAR NOT NULL,
+
+    PRIMARY KEY (show_id, alias)
+);
+
+CREATE INDEX IF NOT EXISTS idx_show_aliases_alias
+ON show_aliases (LOWER(alias));
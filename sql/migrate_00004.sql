@@ -0,0 +1,4 @@
+ALTER TABLE shows ADD COLUMN category VARCHAR;
+ALTER TABLE shows ADD COLUMN priority INTEGER;
+ALTER TABLE shows ADD COLUMN pp INTEGER;
+ALTER TABLE shows ADD COLUMN script VARCHAR;
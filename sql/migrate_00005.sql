@@ -0,0 +1,26 @@
+ALTER TABLE shows ADD COLUMN specials BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE episodes_new (
+    show_id BIGINT NOT NULL REFERENCES shows (show_id),
+    season INTEGER NOT NULL DEFAULT 1,
+    episode INTEGER NOT NULL,
+    kind VARCHAR NOT NULL DEFAULT 'regular',
+    -- hundredths of an episode, e.g. 50 for "12.5" and 5 for "12.05", 0 for none
+    part INTEGER NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 1,
+
+    link VARCHAR NOT NULL,
+    grabbed BOOLEAN NOT NULL DEFAULT FALSE,
+    grabbed_on TIMESTAMP WITHOUT TIME ZONE,
+
+    PRIMARY KEY (show_id, season, kind, episode, part, version)
+);
+
+INSERT INTO episodes_new
+(show_id, season, episode, version, link, grabbed, grabbed_on)
+SELECT show_id, season, episode, version, link, grabbed, grabbed_on
+FROM episodes;
+
+DROP TABLE episodes;
+
+ALTER TABLE episodes_new RENAME TO episodes;
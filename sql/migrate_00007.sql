@@ -0,0 +1,10 @@
+ALTER TABLE shows ADD COLUMN require_attributes VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE shows ADD COLUMN reject_attributes VARCHAR NOT NULL DEFAULT '';
+
+ALTER TABLE episodes ADD COLUMN video_codec VARCHAR;
+ALTER TABLE episodes ADD COLUMN bit_depth INTEGER;
+ALTER TABLE episodes ADD COLUMN source VARCHAR;
+ALTER TABLE episodes ADD COLUMN audio_codec VARCHAR;
+ALTER TABLE episodes ADD COLUMN subtitles VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE episodes ADD COLUMN raw BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE episodes ADD COLUMN uncensored BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE shows ADD COLUMN merge_seasons BOOLEAN NOT NULL DEFAULT FALSE;
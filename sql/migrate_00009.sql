@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS source_pub_dates (
+    source VARCHAR PRIMARY KEY,
+    last_pub_date TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
@@ -1,20 +1,44 @@
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::process;
 
+use chrono::{NaiveDate, NaiveDateTime};
+
 use wildmatch::WildMatch;
 
 use crate::database::{Database, Error as DatabaseError};
 use crate::dognzb;
-use crate::models::Quality;
+use crate::models;
+use crate::models::{
+    AttributeFilter, DownloadOptions, Episode, EpisodeEntry, EpisodeKind, EpisodeRecord, Quality,
+    ReleaseAttributes, ShowStatus,
+};
+use crate::rss;
 use crate::sabnzbd::{Error as SabnzbdError, SabnzbdClient};
-use crate::tosho;
+use crate::sources::{self, Source};
 use crate::utils;
 
 #[derive(Debug)]
 pub enum Error {
-    ToshoError(tosho::Error),
+    SourceError(sources::Error),
     DogError(dognzb::Error),
     DatabaseError(DatabaseError),
     SabnzbdError(SabnzbdError),
+    ShowNotFound(String),
+    AmbiguousShow(String),
+    // pausing or resuming would silently restore it, which is `archive --undo`'s job
+    ShowArchived(String),
+    ShowNotArchived(String),
+    NoSuchResult(usize),
+    BadDate(String),
+    BadEpisodeRange(String),
+    Io(io::Error),
+    JsonError(serde_json::Error),
+    CorpusMismatches(usize),
+    VerifyFailed(usize),
+    // every configured source is torrent-only, leaving nothing to check
+    NoNzbSources,
 }
 
 impl Error {
@@ -24,9 +48,9 @@ impl Error {
     }
 }
 
-impl From<tosho::Error> for Error {
-    fn from(err: tosho::Error) -> Error {
-        Error::ToshoError(err)
+impl From<sources::Error> for Error {
+    fn from(err: sources::Error) -> Error {
+        Error::SourceError(err)
     }
 }
 
@@ -42,24 +66,202 @@ impl From<DatabaseError> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::JsonError(err)
+    }
+}
+
 impl From<SabnzbdError> for Error {
     fn from(err: SabnzbdError) -> Error {
         Error::SabnzbdError(err)
     }
 }
 
-pub fn add(
-    db: &mut Database,
+fn resolve_show(
+    db: &Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+) -> Result<(i64, String, String, Option<Quality>, ShowStatus), Error> {
+    let mut shows = db
+        .find_shows(name)?
+        .into_iter()
+        .filter(|(_, db_group, _, db_quality, _)| {
+            group.is_none_or(|g| g.eq_ignore_ascii_case(db_group))
+                && (quality.is_none() || db_quality == quality)
+        })
+        .collect::<Vec<_>>();
+    match shows.len() {
+        0 => Err(Error::ShowNotFound(name.to_string())),
+        1 => Ok(shows.remove(0)),
+        _ => {
+            eprintln!("Multiple shows match \"{}\", narrow it down with --group or --quality:", name);
+            for (show_id, group, name, quality, _) in &shows {
+                eprintln!(
+                    "{:>4} [{}] {} [{}]",
+                    show_id,
+                    group,
+                    name,
+                    quality
+                        .as_ref()
+                        .map(|q| q.to_string())
+                        .unwrap_or_else(|| "".to_string())
+                );
+            }
+            Err(Error::AmbiguousShow(name.to_string()))
+        }
+    }
+}
+
+// ignores case like `Database::get_show_id` does
+fn name_matches(name: &str, aliases: &[String], ep_name: &str) -> bool {
+    name.eq_ignore_ascii_case(ep_name)
+        || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(ep_name))
+}
+
+// Like `name_matches`, but for shows that merge seasons it also accepts the name with a season
+// marker like "S2" or "2nd Season", moving that season onto the episode.
+fn episode_matches_show(name: &str, aliases: &[String], merge_seasons: bool, ep: &mut Episode) -> bool {
+    if name_matches(name, aliases, &ep.name) {
+        return true;
+    }
+    if !merge_seasons {
+        return false;
+    }
+    match utils::normalize_season(&ep.name) {
+        Some((base, season)) if name_matches(name, aliases, &base) => {
+            ep.name = base;
+            ep.season = ep.season.or(Some(season));
+            true
+        }
+        _ => false,
+    }
+}
+
+// Finds the tracked show an episode belongs to, falling back to the name without its season
+// marker for shows that merge seasons.
+fn find_episode_show(db: &Database, ep: &mut Episode) -> Result<Option<i64>, Error> {
+    if let Some(show_id) = db.get_show_id(&ep.group, &ep.name, &ep.quality)? {
+        return Ok(Some(show_id));
+    }
+    if let Some((base, season)) = utils::normalize_season(&ep.name) {
+        if let Some(show_id) = db.get_show_id(&ep.group, &base, &ep.quality)? {
+            if db.get_merge_seasons(show_id)? {
+                ep.name = base;
+                ep.season = ep.season.or(Some(season));
+                return Ok(Some(show_id));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn group_matches(group: &str, ep_group: &str) -> bool {
+    if group.contains('*') {
+        WildMatch::new(group).matches(ep_group)
+    } else {
+        group == ep_group
+    }
+}
+
+// Switches wanted episodes over to a batch release when it covers more than one of them,
+// adding entries for any episodes in the batch's range that the search didn't turn up.
+fn cover_with_batches(
+    episodes: &mut Vec<EpisodeEntry>,
+    batches: &[(Episode, String)],
+    start_season: i32,
+    start_episode: i32,
+) {
+    for (batch, link) in batches {
+        let range = match batch.range {
+            Some(range) => range,
+            None => continue,
+        };
+        let season = batch.season.unwrap_or(1);
+        if season < start_season {
+            continue;
+        }
+        let wanted = |episode: i32| season > start_season || episode >= start_episode;
+        let mut covered: Vec<i32> = match range.last {
+            Some(last) => (range.first..=last).filter(|ep| wanted(*ep)).collect(),
+            None => episodes
+                .iter()
+                .filter(|ep| {
+                    ep.kind == EpisodeKind::Regular
+                        && ep.season == season
+                        && !ep.grabbed
+                        && range.contains(ep.episode)
+                })
+                .map(|ep| ep.episode)
+                .collect(),
+        };
+        covered.sort_unstable();
+        covered.dedup();
+        if covered.len() < 2 {
+            continue;
+        }
+        println!(
+            "Using batch [{}] {} - S{:02} {} for {} episodes",
+            batch.group,
+            batch.name,
+            season,
+            batch.label(),
+            covered.len()
+        );
+        for episode in &covered {
+            let mut found = false;
+            for ep in episodes.iter_mut().filter(|ep| {
+                ep.kind == EpisodeKind::Regular && ep.season == season && ep.episode == *episode
+            }) {
+                // a batch's checksum isn't known per episode
+                ep.link = link.clone();
+                ep.crc32 = None;
+                ep.attributes = batch.attributes.clone();
+                found = true;
+            }
+            if !found {
+                episodes.push(EpisodeEntry {
+                    season,
+                    episode: *episode,
+                    kind: EpisodeKind::Regular,
+                    part: None,
+                    version: batch.version,
+                    crc32: None,
+                    attributes: batch.attributes.clone(),
+                    link: link.clone(),
+                    grabbed: false,
+                });
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_show_episodes(
+    sources: &[Box<dyn Source>],
     group: &str,
     name: &str,
+    aliases: &[String],
     start_season: i32,
     start_episode: i32,
     quality: &Option<Quality>,
-) -> Result<(), Error> {
-    println!("[{}] {} - S{:02}E{:02} [{:?}]", group, name, start_season, start_episode, quality);
+    specials: bool,
+    merge_seasons: bool,
+    filter: &AttributeFilter,
+) -> Result<Vec<EpisodeEntry>, Error> {
     let mut done = false;
+    let mut filtered: Vec<EpisodeEntry> = Vec::new();
+    let mut batches: Vec<(Episode, String)> = Vec::new();
     for page in 1..10 {
-        let items = tosho::search(
+        let items = sources::search_all(
+            sources,
             &[
                 group, " ", name, " ",
                 // &match quality {
@@ -71,51 +273,307 @@ pub fn add(
             .join(" "),
             Some(page),
         )?;
-        let mut filtered: Vec<(i32, i32, i32, String, bool)> = Vec::new();
         for item in items {
-            if let Some(ep) = utils::match_title(&item.title) {
-                if group.contains('*') {
-                    if !WildMatch::new(group).matches(&ep.group) {
-                        continue;
+            if let Some(mut ep) = utils::match_title(&item.title) {
+                if !group_matches(group, &ep.group) {
+                    continue;
+                }
+                if !episode_matches_show(name, aliases, merge_seasons, &mut ep)
+                    || &ep.quality != quality
+                {
+                    continue;
+                }
+                if !filter.allows(&ep.attributes) {
+                    continue;
+                }
+                if ep.is_batch() {
+                    if !item.nzb_link.is_empty() {
+                        batches.push((ep, item.nzb_link));
                     }
-                } else if ep.group != group {
                     continue;
                 }
-                if ep.name != name || &ep.quality != quality {
+                if ep.kind != EpisodeKind::Regular && !specials {
                     continue;
                 }
                 let ep_season = ep.season.unwrap_or(1);
                 println!(
-                    "{} {} S{:02}E{:02} v{} {:?} {:?}",
-                    ep.group, ep.name, ep_season, ep.episode, ep.version, ep.quality, ep.extension
+                    "{} {} {} v{} {:?} {:?}",
+                    ep.group,
+                    ep.name,
+                    models::season_episode_label(ep_season, ep.kind, ep.episode, ep.part),
+                    ep.version,
+                    ep.quality,
+                    ep.extension
                 );
                 // TODO: what am i actually doing with the * here?
-                filtered.push((
-                    ep_season,
-                    ep.episode,
-                    ep.version,
-                    item.nzb_link.to_string(),
-                    ep_season < start_season || (ep_season == start_season && ep.episode < start_episode),
-                ));
-                done = done || ep_season == start_season && ep.episode == start_episode;
+                filtered.extend(ep.entries(&item.nzb_link, false).into_iter().map(|mut entry| {
+                    entry.grabbed = ep_season < start_season
+                        || (ep_season == start_season && entry.episode < start_episode);
+                    entry
+                }));
+                done = done
+                    || ep.kind == EpisodeKind::Regular
+                        && ep_season == start_season
+                        && ep.episodes.contains(&start_episode);
             }
         }
-        db.add_show_and_episodes(group, name, quality, &filtered)?;
         if done {
             break;
         }
     }
+    cover_with_batches(&mut filtered, &batches, start_season, start_episode);
+    Ok(filtered)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn add(
+    db: &mut Database,
+    sources: &[Box<dyn Source>],
+    group: &str,
+    name: &str,
+    start_season: i32,
+    start_episode: i32,
+    quality: &Option<Quality>,
+    download: &DownloadOptions,
+    specials: bool,
+    merge_seasons: bool,
+    filter: &AttributeFilter,
+) -> Result<(), Error> {
+    println!("[{}] {} - S{:02}E{:02} [{:?}]", group, name, start_season, start_episode, quality);
+    // archived shows count too, so adding one again restores it rather than duplicating it
+    let existing = db.find_shows(name)?.into_iter().find(|(_, db_group, _, db_quality, _)| {
+        group_matches(db_group, group) && db_quality == quality
+    });
+    if let Some((show_id, db_group, db_name, _, status)) = existing {
+        if status == ShowStatus::Archived {
+            db.set_status(show_id, ShowStatus::Active)?;
+            println!("Restored archived [{}] {}, adding episodes to it", db_group, db_name);
+        } else {
+            println!("Already tracking as [{}] {}, adding episodes to it", db_group, db_name);
+        }
+        if download != &DownloadOptions::default() {
+            set_download_options(db, show_id, download.clone(), false)?;
+        }
+        if specials && !db.get_specials(show_id)? {
+            set_specials(db, show_id, true)?;
+        }
+        if merge_seasons && !db.get_merge_seasons(show_id)? {
+            set_merge_seasons(db, show_id, true)?;
+        }
+        let mut stored = db.get_attribute_filter(show_id)?;
+        if filter != &AttributeFilter::default() {
+            // like `edit`, only replace the lists that were given
+            if !filter.require.is_empty() {
+                stored.require = filter.require.clone();
+            }
+            if !filter.reject.is_empty() {
+                stored.reject = filter.reject.clone();
+            }
+            db.set_attribute_filter(show_id, &stored)?;
+            println!(
+                "Requiring [{}], rejecting [{}]",
+                stored.require.join(","),
+                stored.reject.join(",")
+            );
+        }
+        let filter = stored;
+        let mut aliases = db.get_aliases(show_id)?;
+        aliases.push(db_name);
+        let specials = specials || db.get_specials(show_id)?;
+        let merge_seasons = merge_seasons || db.get_merge_seasons(show_id)?;
+        let episodes = search_show_episodes(
+            sources,
+            group,
+            name,
+            &aliases,
+            start_season,
+            start_episode,
+            quality,
+            specials,
+            merge_seasons,
+            &filter,
+        )?;
+        db.add_show_episodes(show_id, &episodes)?;
+        return Ok(());
+    }
+    let episodes = search_show_episodes(
+        sources,
+        group,
+        name,
+        &[],
+        start_season,
+        start_episode,
+        quality,
+        specials,
+        merge_seasons,
+        filter,
+    )?;
+    db.add_show_and_episodes(
+        group,
+        name,
+        quality,
+        download,
+        specials,
+        merge_seasons,
+        filter,
+        &episodes,
+    )?;
     Ok(())
 }
 
-pub fn check(db: &mut Database) -> Result<(), Error> {
-    let last_pub_date = db.get_last_pub_date()?;
+pub fn edit(
+    db: &Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+    new_group: Option<&str>,
+    new_name: Option<&str>,
+    new_quality: Option<Option<Quality>>,
+) -> Result<i64, Error> {
+    let (show_id, group, name, quality, _) = resolve_show(db, name, group, quality)?;
+    let group = new_group.map(str::to_string).unwrap_or(group);
+    let name = new_name.map(str::to_string).unwrap_or(name);
+    let quality = new_quality.unwrap_or(quality);
+    db.update_show(show_id, &group, &name, &quality)?;
+    println!("Updated [{}] {} [{:?}]", group, name, quality);
+    Ok(show_id)
+}
+
+pub fn set_download_options(
+    db: &Database,
+    show_id: i64,
+    download: DownloadOptions,
+    reset: bool,
+) -> Result<(), Error> {
+    let current = if reset {
+        DownloadOptions::default()
+    } else {
+        db.get_download_options(show_id)?
+    };
+    let download = download.or(&current);
+    db.set_download_options(show_id, &download)?;
+    println!(
+        "Download options: category {:?} priority {:?} pp {:?} script {:?}",
+        download.category, download.priority, download.pp, download.script
+    );
+    Ok(())
+}
+
+pub fn set_attribute_filter(
+    db: &Database,
+    show_id: i64,
+    require: Option<&str>,
+    reject: Option<&str>,
+) -> Result<(), Error> {
+    let mut filter = db.get_attribute_filter(show_id)?;
+    if let Some(require) = require {
+        filter.require = models::parse_tags(require);
+    }
+    if let Some(reject) = reject {
+        filter.reject = models::parse_tags(reject);
+    }
+    db.set_attribute_filter(show_id, &filter)?;
+    println!(
+        "Requiring [{}], rejecting [{}]",
+        filter.require.join(","),
+        filter.reject.join(",")
+    );
+    Ok(())
+}
+
+pub fn set_merge_seasons(db: &Database, show_id: i64, merge_seasons: bool) -> Result<(), Error> {
+    db.set_merge_seasons(show_id, merge_seasons)?;
+    if merge_seasons {
+        println!("Matching names with season markers");
+    } else {
+        println!("Matching the exact name only");
+    }
+    Ok(())
+}
+
+pub fn set_specials(db: &Database, show_id: i64, specials: bool) -> Result<(), Error> {
+    db.set_specials(show_id, specials)?;
+    if specials {
+        println!("Tracking specials");
+    } else {
+        println!("Tracking regular episodes only");
+    }
+    Ok(())
+}
+
+pub fn backfill(
+    db: &mut Database,
+    sources: &[Box<dyn Source>],
+    show_id: i64,
+    start_season: Option<i32>,
+    start_episode: Option<i32>,
+) -> Result<(), Error> {
+    let (group, name, quality) = match db.get_show(show_id)? {
+        Some(show) => show,
+        None => return Err(Error::ShowNotFound(show_id.to_string())),
+    };
+    // default to picking up right after the latest episode we already know about
+    let (latest_season, latest_episode) = db.get_latest_episode(show_id)?.unwrap_or((1, 0));
+    let start_season = start_season.unwrap_or(latest_season);
+    let start_episode = start_episode.unwrap_or(if start_season == latest_season {
+        latest_episode + 1
+    } else {
+        1
+    });
+    println!(
+        "Backfilling [{}] {} - S{:02}E{:02} [{:?}]",
+        group, name, start_season, start_episode, quality
+    );
+    let aliases = db.get_aliases(show_id)?;
+    let specials = db.get_specials(show_id)?;
+    let merge_seasons = db.get_merge_seasons(show_id)?;
+    let filter = db.get_attribute_filter(show_id)?;
+    let episodes = search_show_episodes(
+        sources,
+        &group,
+        &name,
+        &aliases,
+        start_season,
+        start_episode,
+        &quality,
+        specials,
+        merge_seasons,
+        &filter,
+    )?;
+    db.add_show_episodes(show_id, &episodes)?;
+    Ok(())
+}
+
+// keeps one entry per episode when several sources find it, preferring one with an nzb link
+fn push_new_episode(new_episodes: &mut Vec<(i64, EpisodeEntry)>, show_id: i64, entry: EpisodeEntry) {
+    let existing = new_episodes.iter_mut().find(|(id, e)| {
+        *id == show_id
+            && e.season == entry.season
+            && e.episode == entry.episode
+            && e.kind == entry.kind
+            && e.part == entry.part
+            && e.version == entry.version
+    });
+    match existing {
+        Some((_, e)) if e.link.is_empty() => *e = entry,
+        Some(_) => {}
+        None => new_episodes.push((show_id, entry)),
+    }
+}
+
+// pages through a source's feed back to where the last check left off, returning the newest
+// pub date seen
+fn check_source(
+    db: &Database,
+    source: &dyn Source,
+    new_episodes: &mut Vec<(i64, EpisodeEntry)>,
+) -> Result<NaiveDateTime, Error> {
+    let last_pub_date = db.get_last_pub_date(source.name())?;
     let mut newest_pub_date = last_pub_date;
     let mut page = 1;
-    let mut new_episodes: Vec<(i64, Option<i32>, i32, i32, String)> = Vec::new();
     'outer: loop {
-        println!("getting feed page: {}", page);
-        let items = tosho::feed(&page)?;
+        println!("getting {} feed page: {}", source.name(), page);
+        let items = source.feed(page)?;
         if items.is_empty() {
             break 'outer;
         }
@@ -126,13 +584,22 @@ pub fn check(db: &mut Database) -> Result<(), Error> {
             if item.pub_date > newest_pub_date {
                 newest_pub_date = item.pub_date;
             }
-            if let Some(ep) = utils::match_title(&item.title) {
-                if let Some(show_id) = db.get_show_id(&ep.group, &ep.name, &ep.quality)? {
+            if let Some(mut ep) = utils::match_title(&item.title) {
+                if ep.is_batch() {
+                    continue;
+                }
+                if let Some(show_id) = find_episode_show(db, &mut ep)? {
+                    if ep.kind != EpisodeKind::Regular && !db.get_specials(show_id)? {
+                        continue;
+                    }
+                    if !db.get_attribute_filter(show_id)?.allows(&ep.attributes) {
+                        continue;
+                    }
                     print!(
                         "Found [{}] {} - {} v{} [{}]",
                         ep.group,
                         ep.name,
-                        ep.episode,
+                        ep.label(),
                         ep.version,
                         ep.quality
                             .map(|q| q.to_string())
@@ -143,36 +610,77 @@ pub fn check(db: &mut Database) -> Result<(), Error> {
                     } else {
                         println!();
                     }
-                    new_episodes.push((show_id, ep.season, ep.episode, ep.version, item.nzb_link.to_string()));
+                    for entry in ep.entries(&item.nzb_link, false) {
+                        push_new_episode(new_episodes, show_id, entry);
+                    }
                 }
             }
         }
         page += 1;
     }
+    Ok(newest_pub_date)
+}
+
+pub fn check(db: &mut Database, sources: &[Box<dyn Source>]) -> Result<(), Error> {
+    if sources.is_empty() {
+        return Err(Error::NoNzbSources);
+    }
+    let mut new_episodes: Vec<(i64, EpisodeEntry)> = Vec::new();
+    let mut pub_dates: Vec<(&str, NaiveDateTime)> = Vec::new();
+    let mut last_err = None;
+    for source in sources {
+        match check_source(db, source.as_ref(), &mut new_episodes) {
+            Ok(pub_date) => pub_dates.push((source.name(), pub_date)),
+            Err(err) => {
+                eprintln!("{} failed: {:?}", source.name(), err);
+                last_err = Some(err);
+            }
+        }
+    }
     if !new_episodes.is_empty() {
         db.add_episodes(&new_episodes)?;
     }
-    db.set_last_pub_date(&newest_pub_date)?;
-    Ok(())
+    for (source, pub_date) in &pub_dates {
+        db.set_last_pub_date(source, pub_date)?;
+    }
+    match last_err {
+        Some(err) if pub_dates.is_empty() => Err(err),
+        _ => Ok(()),
+    }
 }
 
-pub fn recheck(db: &mut Database, page: u8) -> Result<(), Error> {
-    let mut new_episodes: Vec<(i64, Option<i32>, i32, i32, String)> = Vec::new();
+pub fn recheck(db: &mut Database, sources: &[Box<dyn Source>], page: u8) -> Result<(), Error> {
+    if sources.is_empty() {
+        return Err(Error::NoNzbSources);
+    }
+    let mut new_episodes: Vec<(i64, EpisodeEntry)> = Vec::new();
     println!("getting feed page: {}", page);
-    let items = tosho::feed(&page)?;
+    let items = sources::feed_all(sources, page)?;
     for item in items {
-        if let Some(ep) = utils::match_title(&item.title) {
+        if let Some(mut ep) = utils::match_title(&item.title) {
             //dbg!(&ep);
-            if let Some(show_id) = db.get_show_id(&ep.group, &ep.name, &ep.quality)? {
-                if db
-                    .get_episode(&show_id, &ep.episode, &ep.version)?
-                    .is_none()
-                {
+            if ep.is_batch() {
+                continue;
+            }
+            if let Some(show_id) = find_episode_show(db, &mut ep)? {
+                if ep.kind != EpisodeKind::Regular && !db.get_specials(show_id)? {
+                    continue;
+                }
+                if !db.get_attribute_filter(show_id)?.allows(&ep.attributes) {
+                    continue;
+                }
+                let mut entries = Vec::new();
+                for entry in ep.entries(&item.nzb_link, false) {
+                    if db.get_episode(&show_id, &entry)?.is_none() {
+                        entries.push(entry);
+                    }
+                }
+                if !entries.is_empty() {
                     print!(
                         "Found [{}] {} - {} v{} [{}]",
                         ep.group,
                         ep.name,
-                        ep.episode,
+                        ep.label(),
                         ep.version,
                         ep.quality
                             .map(|q| q.to_string())
@@ -183,13 +691,13 @@ pub fn recheck(db: &mut Database, page: u8) -> Result<(), Error> {
                     } else {
                         println!();
                     }
-                    new_episodes.push((show_id, ep.season, ep.episode, ep.version, item.nzb_link.to_string()));
+                    new_episodes.extend(entries.into_iter().map(|entry| (show_id, entry)));
                 } else {
                     println!(
                         "Skipping existing [{}] {} - {} v{} [{}]",
                         ep.group,
                         ep.name,
-                        ep.episode,
+                        ep.label(),
                         ep.version,
                         ep.quality
                             .map(|q| q.to_string())
@@ -205,66 +713,255 @@ pub fn recheck(db: &mut Database, page: u8) -> Result<(), Error> {
     Ok(())
 }
 
-pub fn check_missing(db: &mut Database) -> Result<(), Error> {
+fn find_missing_batches(
+    db: &Database,
+    sources: &[Box<dyn Source>],
+    missing_episodes: &[EpisodeRecord],
+    new_episodes: &mut Vec<(i64, EpisodeEntry)>,
+) -> Result<(), Error> {
+    let mut show_ids: Vec<i64> = missing_episodes.iter().map(|ep| ep.show_id).collect();
+    show_ids.sort_unstable();
+    show_ids.dedup();
+    for show_id in show_ids {
+        let show_missing: Vec<&EpisodeRecord> = missing_episodes
+            .iter()
+            .filter(|ep| ep.show_id == show_id && ep.kind == EpisodeKind::Regular)
+            .collect();
+        // a batch is only worth it when it covers several missing episodes
+        if show_missing.len() < 2 {
+            continue;
+        }
+        let (group, name, quality) = {
+            let ep = show_missing[0];
+            (&ep.group, &ep.name, &ep.quality)
+        };
+        println!("Checking for batches: [{}] {}", group, name);
+        let aliases = db.get_aliases(show_id)?;
+        let merge_seasons = db.get_merge_seasons(show_id)?;
+        let filter = db.get_attribute_filter(show_id)?;
+        for search_name in std::iter::once(name).chain(aliases.iter()) {
+            let terms = [
+                group.as_str(),
+                search_name,
+                &quality
+                    .as_ref()
+                    .map(|q| q.to_string())
+                    .unwrap_or_else(|| "".to_string()),
+            ]
+            .join(" ");
+            let mut found = false;
+            for item in sources::search_all(sources, &terms, Some(1))? {
+                if item.nzb_link.is_empty() {
+                    continue;
+                }
+                let mut ep = match utils::match_title(&item.title) {
+                    Some(ep) => ep,
+                    None => continue,
+                };
+                let range = match ep.range {
+                    Some(range) => range,
+                    None => continue,
+                };
+                if !group_matches(group, &ep.group)
+                    || !episode_matches_show(name, &aliases, merge_seasons, &mut ep)
+                    || &ep.quality != quality
+                    || !filter.allows(&ep.attributes)
+                {
+                    continue;
+                }
+                let season = ep.season.unwrap_or(1);
+                let covered: Vec<&&EpisodeRecord> = show_missing
+                    .iter()
+                    .filter(|m| {
+                        m.season == season
+                            && range.contains(m.episode)
+                            && !new_episodes.iter().any(|(id, e)| {
+                                *id == show_id
+                                    && e.season == m.season
+                                    && e.kind == m.kind
+                                    && e.episode == m.episode
+                            })
+                    })
+                    .collect();
+                if covered.len() < 2 {
+                    continue;
+                }
+                println!(
+                    "Found batch [{}] {} - S{:02} {} covering {} episodes",
+                    ep.group,
+                    ep.name,
+                    season,
+                    ep.label(),
+                    covered.len()
+                );
+                for m in covered {
+                    new_episodes.push((
+                        show_id,
+                        EpisodeEntry {
+                            season: m.season,
+                            episode: m.episode,
+                            kind: m.kind,
+                            part: m.part,
+                            version: m.version,
+                            crc32: None,
+                            attributes: ep.attributes.clone(),
+                            link: item.nzb_link.clone(),
+                            grabbed: false,
+                        },
+                    ));
+                }
+                found = true;
+            }
+            if found {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+// looks through search results for a missing episode, adding it along with any other
+// episodes in the same release to `new_episodes`
+fn find_missing_episode(
+    items: Vec<rss::Item>,
+    episode: &EpisodeRecord,
+    aliases: &[String],
+    merge_seasons: bool,
+    filter: &AttributeFilter,
+    new_episodes: &mut Vec<(i64, EpisodeEntry)>,
+) -> bool {
+    let mut found = false;
+    for item in items {
+        if item.nzb_link.is_empty() {
+            continue;
+        }
+        if let Some(mut ep) = utils::match_title(&item.title) {
+            if ep.is_batch()
+                || !group_matches(&episode.group, &ep.group)
+                || !filter.allows(&ep.attributes)
+            {
+                continue;
+            }
+            if episode_matches_show(&episode.name, aliases, merge_seasons, &mut ep)
+                && ep.season.is_none_or(|season| season == episode.season)
+                && ep.episodes.contains(&episode.episode)
+                && ep.kind == episode.kind
+                && ep.part == episode.part
+                && ep.quality == episode.quality
+            {
+                println!(
+                    "Found [{}] {} - {} v{} [{}]",
+                    ep.group,
+                    ep.name,
+                    ep.label(),
+                    ep.version,
+                    ep.quality
+                        .map(|q| q.to_string())
+                        .unwrap_or_else(|| "".to_string())
+                );
+                for entry in ep.entries(&item.nzb_link, false) {
+                    new_episodes.push((episode.show_id, entry));
+                }
+                found = true;
+            }
+        }
+    }
+    found
+}
+
+pub fn check_missing(
+    db: &mut Database,
+    sources: &[Box<dyn Source>],
+    fallbacks: &[Box<dyn Source>],
+) -> Result<(), Error> {
+    if sources.is_empty() && fallbacks.is_empty() {
+        return Err(Error::NoNzbSources);
+    }
     let missing_episodes = db.list_episodes_missing_nzb()?;
-    let mut new_episodes: Vec<(i64, Option<i32>, i32, i32, String)> = Vec::new();
+    let mut new_episodes: Vec<(i64, EpisodeEntry)> = Vec::new();
+    find_missing_batches(db, sources, &missing_episodes, &mut new_episodes)?;
     for episode in missing_episodes {
-        let show_id = episode.0;
-        let name = episode.1;
-        let group = episode.2;
-        let quality = episode.3;
-        let episode_no = episode.4;
-        let version = episode.5;
+        if new_episodes.iter().any(|(id, e)| {
+            *id == episode.show_id
+                && e.season == episode.season
+                && e.kind == episode.kind
+                && e.episode == episode.episode
+                && e.part == episode.part
+        }) {
+            continue;
+        }
+        let show_id = episode.show_id;
+        let name = &episode.name;
+        let group = &episode.group;
+        let quality = &episode.quality;
         println!(
             "Checking for: [{}] {} - {} v{} [{}]",
             group,
             name,
-            episode_no,
-            version,
+            episode.label(),
+            episode.version,
             quality
                 .as_ref()
                 .map(|q| q.to_string())
                 .unwrap_or_else(|| "".to_string())
         );
 
-        let terms = {
-            let arr: [&str; 4] = [
-                &group,
-                &name,
-                &format!("{:02}", episode_no),
-                &quality
-                    .as_ref()
-                    .map(|q| q.to_string())
-                    .unwrap_or_else(|| "".to_string()),
-            ];
-            arr.join(" ")
-        };
-        let results = tosho::search(&terms, Some(1))?;
-        for item in results {
-            if item.nzb_link.is_empty() {
-                continue;
+        let aliases = db.get_aliases(show_id)?;
+        let merge_seasons = db.get_merge_seasons(show_id)?;
+        let filter = db.get_attribute_filter(show_id)?;
+        let mut found = false;
+        for search_name in std::iter::once(name).chain(aliases.iter()) {
+            let terms = {
+                let arr: [&str; 4] = [
+                    group,
+                    search_name,
+                    &episode.label(),
+                    &quality
+                        .as_ref()
+                        .map(|q| q.to_string())
+                        .unwrap_or_else(|| "".to_string()),
+                ];
+                arr.join(" ")
+            };
+            let results = sources::search_all(sources, &terms, Some(1))?;
+            found = find_missing_episode(
+                results,
+                &episode,
+                &aliases,
+                merge_seasons,
+                &filter,
+                &mut new_episodes,
+            );
+            if found {
+                break;
             }
-            if let Some(ep) = utils::match_title(&item.title) {
-                if group.contains('*') {
-                    if !WildMatch::new(&group).matches(&ep.group) {
-                        continue;
-                    }
-                } else if ep.group != group {
-                    continue;
-                }
-                if ep.name == name && ep.episode == episode_no && ep.quality == quality {
-                    println!(
-                        "Found [{}] {} - {} v{} [{}]",
-                        ep.group,
-                        ep.name,
-                        ep.episode,
-                        ep.version,
-                        ep.quality
-                            .map(|q| q.to_string())
-                            .unwrap_or_else(|| "".to_string())
-                    );
-                    new_episodes.push((show_id, ep.season, ep.episode, ep.version, item.nzb_link.to_string()));
-                }
+        }
+        if found || fallbacks.is_empty() {
+            continue;
+        }
+        println!("Trying fallback sources");
+        for search_name in std::iter::once(name).chain(aliases.iter()) {
+            let terms = [group.as_str(), search_name, &episode.label()].join(" ");
+            let results = if episode.kind == EpisodeKind::Regular && episode.part.is_none() {
+                sources::search_episode_all(
+                    fallbacks,
+                    search_name,
+                    episode.season,
+                    episode.episode,
+                    &terms,
+                )?
+            } else {
+                sources::search_all(fallbacks, &terms, Some(1))?
+            };
+            if find_missing_episode(
+                results,
+                &episode,
+                &aliases,
+                merge_seasons,
+                &filter,
+                &mut new_episodes,
+            ) {
+                break;
             }
         }
     }
@@ -274,14 +971,18 @@ pub fn check_missing(db: &mut Database) -> Result<(), Error> {
     Ok(())
 }
 
-pub fn queue(db: &Database, sabnzbd: &SabnzbdClient) -> Result<(), Error> {
-    for (show_id, ep_no, url) in db.list_ungrapped_nzbs()? {
+pub fn queue(
+    db: &Database,
+    sabnzbd: &SabnzbdClient,
+    defaults: &DownloadOptions,
+) -> Result<(), Error> {
+    for (show_id, url, download) in db.list_ungrapped_nzbs()? {
         if url.is_empty() {
             continue;
         }
         println!("Grabbing: {}", url);
-        sabnzbd.addurl(&url, "anime")?;
-        db.mark_grabbed(show_id, ep_no)?;
+        sabnzbd.addurl(&url, &download.or(defaults))?;
+        db.mark_link_grabbed(show_id, &url)?;
     }
     Ok(())
 }
@@ -289,7 +990,493 @@ pub fn queue(db: &Database, sabnzbd: &SabnzbdClient) -> Result<(), Error> {
 pub fn dog(apikey: &str, sabnzbd: &SabnzbdClient) -> Result<(), Error> {
     for item in dognzb::get_bookmarks(apikey)? {
         println!("Grabbing: {}", item.title);
-        sabnzbd.addurl(&item.link, "")?;
+        sabnzbd.addurl(&item.link, &DownloadOptions::default())?;
+    }
+    Ok(())
+}
+
+pub fn list(
+    db: &Database,
+    group: Option<&str>,
+    name: Option<&str>,
+    ungrabbed_only: bool,
+) -> Result<(), Error> {
+    let group = group.map(|g| g.to_lowercase());
+    let name = name.map(|n| WildMatch::new(&n.to_lowercase()));
+    for show in db.list_shows()? {
+        if let Some(group) = &group {
+            let db_group = show.group.to_lowercase();
+            let matches = WildMatch::new(group).matches(&db_group)
+                || (db_group.contains('*') && WildMatch::new(&db_group).matches(group));
+            if !matches {
+                continue;
+            }
+        }
+        if let Some(name) = &name {
+            if !name.matches(&show.name.to_lowercase()) {
+                continue;
+            }
+        }
+        if ungrabbed_only && show.ungrabbed == 0 {
+            continue;
+        }
+        let latest = match (show.latest_season, show.latest_episode, show.latest_version) {
+            (Some(season), Some(episode), Some(version)) => {
+                format!("S{:02}E{:02}v{}", season, episode, version)
+            }
+            _ => "-".to_string(),
+        };
+        println!(
+            "{:>4} [{}] {} [{}] latest: {} grabbed: {}/{} last grabbed: {}{}",
+            show.show_id,
+            show.group,
+            show.name,
+            show.quality
+                .map(|q| q.to_string())
+                .unwrap_or_else(|| "".to_string()),
+            latest,
+            show.grabbed,
+            show.grabbed + show.ungrabbed,
+            show.last_grabbed_on
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "never".to_string()),
+            match show.status {
+                ShowStatus::Active => "",
+                ShowStatus::Paused => " (paused)",
+                ShowStatus::Archived => " (archived)",
+            }
+        );
+    }
+    Ok(())
+}
+
+pub fn remove(
+    db: &mut Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+) -> Result<(), Error> {
+    let (show_id, group, name, _, _) = resolve_show(db, name, group, quality)?;
+    db.remove_show(show_id)?;
+    println!("Removed [{}] {}", group, name);
+    Ok(())
+}
+
+pub fn archive(
+    db: &Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+    undo: bool,
+) -> Result<(), Error> {
+    let (show_id, group, name, _, status) = resolve_show(db, name, group, quality)?;
+    if undo && status != ShowStatus::Archived {
+        return Err(Error::ShowNotArchived(name));
+    }
+    let status = if undo { ShowStatus::Active } else { ShowStatus::Archived };
+    db.set_status(show_id, status)?;
+    if undo {
+        println!("Restored [{}] {}", group, name);
+    } else {
+        println!("Archived [{}] {}", group, name);
+    }
+    Ok(())
+}
+
+pub fn set_status(
+    db: &Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+    status: ShowStatus,
+) -> Result<(), Error> {
+    let (show_id, group, name, _, current) = resolve_show(db, name, group, quality)?;
+    if current == ShowStatus::Archived {
+        return Err(Error::ShowArchived(name));
+    }
+    db.set_status(show_id, status)?;
+    match status {
+        ShowStatus::Active => println!("Resumed [{}] {}", group, name),
+        ShowStatus::Paused => println!("Paused [{}] {}", group, name),
+        ShowStatus::Archived => println!("Archived [{}] {}", group, name),
+    }
+    Ok(())
+}
+
+pub fn add_alias(
+    db: &Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+    alias: &str,
+) -> Result<(), Error> {
+    let (show_id, group, name, _, _) = resolve_show(db, name, group, quality)?;
+    db.add_alias(show_id, alias)?;
+    println!("Added alias \"{}\" for [{}] {}", alias, group, name);
+    Ok(())
+}
+
+pub fn remove_alias(
+    db: &Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+    alias: &str,
+) -> Result<(), Error> {
+    let (show_id, group, name, _, _) = resolve_show(db, name, group, quality)?;
+    if db.remove_alias(show_id, alias)? == 0 {
+        println!("[{}] {} has no alias \"{}\"", group, name, alias);
+    } else {
+        println!("Removed alias \"{}\" from [{}] {}", alias, group, name);
+    }
+    Ok(())
+}
+
+pub fn list_aliases(db: &Database, name: Option<&str>) -> Result<(), Error> {
+    let name = name.map(|n| WildMatch::new(&n.to_lowercase()));
+    for (show_id, group, show_name, alias) in db.list_aliases()? {
+        if let Some(name) = &name {
+            if !name.matches(&show_name.to_lowercase()) {
+                continue;
+            }
+        }
+        println!("{:>4} [{}] {} = {}", show_id, group, show_name, alias);
+    }
+    Ok(())
+}
+
+/// Searches `sources`, or `fallbacks` when those find nothing.
+pub fn search(
+    sources: &[Box<dyn Source>],
+    fallbacks: &[Box<dyn Source>],
+    terms: &str,
+    pages: u8,
+) -> Result<Vec<Option<Episode>>, Error> {
+    let mut sources = sources;
+    let mut results = Vec::new();
+    println!(
+        "{:>3}  {:<16} {:<40} {:>3} {:>4} {:>2} {:<7} {:<19} {:<3} {:<3} {:>10} {:>5} {:>11} {:<10} {:<22} title",
+        "#", "group", "name", "s", "ep", "v", "quality", "published", "nzb", "tor", "size", "files",
+        "seeds/leech", "status", "anidb"
+    );
+    for page in 1..=pages {
+        let mut items = sources::search_all(sources, terms, Some(page))?;
+        if items.is_empty() && page == 1 && !fallbacks.is_empty() {
+            sources = fallbacks;
+            items = sources::search_all(sources, terms, Some(page))?;
+        }
+        if items.is_empty() {
+            break;
+        }
+        for item in items {
+            let ep = utils::match_title(&item.title);
+            let (group, name, season, episode, version, quality) = match &ep {
+                Some(ep) => (
+                    ep.group.as_str(),
+                    ep.name.as_str(),
+                    ep.season.map(|s| s.to_string()).unwrap_or_default(),
+                    match &ep.range {
+                        Some(range) => range.to_string(),
+                        None => ep.label(),
+                    },
+                    ep.version.to_string(),
+                    ep.quality
+                        .as_ref()
+                        .map(|q| q.to_string())
+                        .unwrap_or_default(),
+                ),
+                None => ("-", "-", "".to_string(), "".to_string(), "".to_string(), "".to_string()),
+            };
+            let peers = match (item.seeders, item.leechers) {
+                (Some(seeders), Some(leechers)) => format!("{}/{}", seeders, leechers),
+                (Some(seeders), None) => seeders.to_string(),
+                _ => "".to_string(),
+            };
+            // e.g. "a14807 e225612", where animetosho has matched the release on AniDB
+            let anidb = [("a", item.anidb_aid), ("e", item.anidb_eid), ("f", item.anidb_fid)]
+                .iter()
+                .filter_map(|(prefix, id)| id.map(|id| format!("{}{}", prefix, id)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "{:>3}  {:<16} {:<40} {:>3} {:>4} {:>2} {:<7} {:<19} {:<3} {:<3} {:>10} {:>5} {:>11} {:<10} {:<22} {}",
+                results.len() + 1,
+                group,
+                name,
+                season,
+                episode,
+                version,
+                quality,
+                item.pub_date,
+                if item.nzb_link.is_empty() { "no" } else { "yes" },
+                if item.torrent_link.is_empty() { "no" } else { "yes" },
+                item.size.map(rss::format_size).unwrap_or_default(),
+                item.file_count.map(|count| count.to_string()).unwrap_or_default(),
+                peers,
+                item.status.as_deref().unwrap_or(""),
+                anidb,
+                item.title
+            );
+            results.push(ep);
+        }
+    }
+    Ok(results)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn add_search_result(
+    db: &mut Database,
+    sources: &[Box<dyn Source>],
+    results: &[Option<Episode>],
+    index: usize,
+    start_season: i32,
+    start_episode: i32,
+    download: &DownloadOptions,
+    specials: bool,
+    merge_seasons: bool,
+    filter: &AttributeFilter,
+) -> Result<(), Error> {
+    let ep = match index.checked_sub(1).and_then(|i| results.get(i)) {
+        Some(Some(ep)) => ep,
+        _ => return Err(Error::NoSuchResult(index)),
+    };
+    add(
+        db,
+        sources,
+        &ep.group,
+        &ep.name,
+        start_season,
+        start_episode,
+        &ep.quality,
+        download,
+        specials,
+        merge_seasons,
+        filter,
+    )
+}
+
+fn parse_since(since: &str) -> Result<NaiveDateTime, Error> {
+    NaiveDateTime::parse_from_str(since, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(since, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(since, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+        })
+        .map_err(|_| Error::BadDate(since.to_string()))
+}
+
+pub fn history(db: &Database, show: Option<&str>, since: Option<&str>) -> Result<(), Error> {
+    let since = since.map(parse_since).transpose()?;
+    let mut episodes = db.list_history(since.as_ref())?;
+    if let Some(show) = show {
+        let show = WildMatch::new(&show.to_lowercase());
+        episodes.retain(|ep| show.matches(&ep.name.to_lowercase()));
+        episodes.sort_by(|a, b| {
+            (a.name.to_lowercase(), a.show_id, a.season, a.episode, a.version).cmp(&(
+                b.name.to_lowercase(),
+                b.show_id,
+                b.season,
+                b.episode,
+                b.version,
+            ))
+        });
+    }
+    for ep in episodes {
+        println!(
+            "{:<19} {:<11} [{}] {} - {} v{} [{}] {}{}",
+            ep.grabbed_on
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            if ep.grabbed { "grabbed" } else { "not grabbed" },
+            ep.group,
+            ep.name,
+            ep.season_label(),
+            ep.version,
+            ep.quality
+                .map(|q| q.to_string())
+                .unwrap_or_else(|| "".to_string()),
+            ep.link,
+            if ep.link.is_empty() { "(missing nzb)" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn mark(
+    db: &mut Database,
+    name: &str,
+    group: Option<&str>,
+    quality: &Option<Quality>,
+    episodes: &str,
+    season: Option<i32>,
+    kind: EpisodeKind,
+    part: Option<i32>,
+    version: Option<i32>,
+    grabbed: bool,
+    record: bool,
+) -> Result<(), Error> {
+    let (show_id, group, name, _, _) = resolve_show(db, name, group, quality)?;
+    let episodes = match utils::parse_episode_range(episodes) {
+        Some(episodes) => episodes,
+        None => return Err(Error::BadEpisodeRange(episodes.to_string())),
+    };
+    // episode numbers restart each season, so don't mark the same number in all of them
+    let season = match season {
+        Some(season) => season,
+        None => db.get_latest_episode(show_id)?.map_or(1, |(season, _)| season),
+    };
+    for episode in episodes {
+        let marked = if grabbed {
+            db.mark_grabbed(show_id, Some(season), kind, episode, part, version)?
+        } else {
+            db.mark_ungrabbed(show_id, Some(season), kind, episode, part, version)?
+        };
+        let label = models::season_episode_label(season, kind, episode, part);
+        if marked > 0 {
+            println!(
+                "Marked [{}] {} - {} as {}",
+                group,
+                name,
+                label,
+                if grabbed { "grabbed" } else { "not grabbed" }
+            );
+        } else if record {
+            // record episodes fetched outside of tosho so they are never queued
+            let entry = EpisodeEntry {
+                season,
+                episode,
+                kind,
+                part,
+                version: version.unwrap_or(1),
+                crc32: None,
+                attributes: ReleaseAttributes::default(),
+                link: String::new(),
+                grabbed: true,
+            };
+            db.add_show_episodes(show_id, std::slice::from_ref(&entry))?;
+            db.mark_grabbed(
+                show_id,
+                Some(entry.season),
+                kind,
+                episode,
+                part,
+                Some(entry.version),
+            )?;
+            println!(
+                "Recorded [{}] {} - {} v{} as grabbed",
+                group,
+                name,
+                entry.season_label(),
+                entry.version
+            );
+        } else if grabbed {
+            println!(
+                "No episode {} of [{}] {} to mark, add --record to record it as grabbed",
+                label, group, name
+            );
+        } else {
+            println!("No episode {} of [{}] {} to mark", label, group, name);
+        }
+    }
+    Ok(())
+}
+
+pub fn verify(db: &Database, files: &[String], requeue: bool) -> Result<(), Error> {
+    let mut bad = 0;
+    for file in files {
+        let path = Path::new(file);
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file.to_string());
+        let ep = utils::match_title(&file_name);
+        let show_id = match &ep {
+            Some(ep) => db.get_show_id(&ep.group, &ep.name, &ep.quality)?,
+            None => None,
+        };
+        // prefer the checksum recorded when the episode was found over the file name's
+        let stored = match (&ep, show_id) {
+            (Some(ep), Some(show_id)) => db.get_episode_crc32(show_id, &ep.entry("", false))?,
+            _ => None,
+        };
+        let expected = match stored.or_else(|| ep.as_ref().and_then(|ep| ep.crc32)) {
+            Some(expected) => expected,
+            None => {
+                println!("No checksum known for {}", file);
+                continue;
+            }
+        };
+        let actual = utils::file_crc32(path)?;
+        if actual == expected {
+            println!("OK  {}", file);
+            continue;
+        }
+        bad += 1;
+        println!("BAD {} expected {:08X} got {:08X}", file, expected, actual);
+        if !requeue {
+            continue;
+        }
+        match (&ep, show_id) {
+            // a multi-episode file was grabbed as one link, so requeue every episode in it
+            (Some(ep), Some(show_id)) => {
+                for entry in ep.entries("", false) {
+                    let marked = db.mark_ungrabbed(
+                        show_id,
+                        Some(entry.season),
+                        entry.kind,
+                        entry.episode,
+                        entry.part,
+                        Some(entry.version),
+                    )?;
+                    if marked > 0 {
+                        println!(
+                            "Marked {} as not grabbed so queue sends it again",
+                            entry.season_label()
+                        );
+                    } else {
+                        println!(
+                            "No {} recorded for this show, can't requeue it",
+                            entry.season_label()
+                        );
+                    }
+                }
+            }
+            _ => println!("Not tracking {}, can't requeue it", file),
+        }
+    }
+    if bad > 0 {
+        println!("{} of {} files failed verification", bad, files.len());
+        return Err(Error::VerifyFailed(bad));
+    }
+    Ok(())
+}
+
+pub fn parse(titles: &[String]) -> Result<(), Error> {
+    let titles = if titles.is_empty() {
+        io::stdin().lines().collect::<Result<Vec<_>, _>>()?
+    } else {
+        titles.to_vec()
+    };
+    for title in titles.iter().map(|title| title.trim()) {
+        if !title.is_empty() {
+            println!("{}", utils::parse_json(title));
+        }
+    }
+    Ok(())
+}
+
+pub fn replay_corpus(path: &str) -> Result<(), Error> {
+    let (checked, mismatches) = utils::replay_corpus(&fs::read_to_string(path)?)?;
+    for mismatch in &mismatches {
+        println!(
+            "{}:{} {}\n    {}: expected {} got {}",
+            path, mismatch.line, mismatch.title, mismatch.field, mismatch.expected, mismatch.actual
+        );
+    }
+    println!("{} titles checked, {} mismatches", checked, mismatches.len());
+    if !mismatches.is_empty() {
+        return Err(Error::CorpusMismatches(mismatches.len()));
     }
     Ok(())
 }
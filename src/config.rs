@@ -1,4 +1,6 @@
+use crate::models::DownloadOptions;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::io::Read;
@@ -8,12 +10,142 @@ use std::path::PathBuf;
 pub struct Config {
     pub sabnzbd: SabnzbdConfig,
     pub dognzb: DognzbConfig,
+    /// Title parsing rules keyed by release group, e.g. `[groups.SubsPlease]`
+    #[serde(default)]
+    pub groups: HashMap<String, GroupGrammar>,
+    /// Where releases are looked for, e.g. `[[sources]] kind = "animetosho"`;
+    /// animetosho alone when none are listed
+    #[serde(default = "default_sources")]
+    pub sources: Vec<SourceConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SourceConfig {
+    Animetosho {
+        #[serde(default)]
+        format: ToshoFormat,
+    },
+    /// Torrents only, so only `search` uses it
+    Nyaa {
+        /// https://nyaa.si/ by default
+        url: Option<String>,
+        /// "1_2" (Anime - English-translated) by default
+        category: Option<String>,
+        #[serde(default)]
+        trusted_only: bool,
+    },
+    Newznab {
+        /// Identifies the indexer in output and in the database, e.g. "nzbgeek"
+        name: String,
+        /// e.g. "https://api.nzbgeek.info/"
+        url: String,
+        apikey: String,
+        /// e.g. ["5070"] for TV > Anime; everything when empty
+        #[serde(default)]
+        categories: Vec<String>,
+        /// Only searched by `check` for episodes the other sources are missing nzbs for
+        #[serde(default)]
+        fallback: bool,
+    },
+    /// Torrents only, so only `search` uses it
+    Torznab {
+        /// Identifies the indexer in output and in the database, e.g. "prowlarr"
+        name: String,
+        /// e.g. "http://localhost:9696/1/" for a Prowlarr indexer
+        url: String,
+        apikey: String,
+        #[serde(default)]
+        categories: Vec<String>,
+        /// Only searched when the other sources find nothing
+        #[serde(default)]
+        fallback: bool,
+    },
+}
+
+impl SourceConfig {
+    pub fn is_fallback(&self) -> bool {
+        match self {
+            SourceConfig::Newznab { fallback, .. } | SourceConfig::Torznab { fallback, .. } => {
+                *fallback
+            }
+            _ => false,
+        }
+    }
+}
+
+fn default_sources() -> Vec<SourceConfig> {
+    vec![SourceConfig::Animetosho {
+        format: ToshoFormat::default(),
+    }]
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToshoFormat {
+    #[default]
+    Rss,
+    /// Adds sizes, file counts, info hashes, AniDB ids and status, falling back to the rss
+    /// feed when it fails
+    Json,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SabnzbdConfig {
     pub url: String,
     pub apikey: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    pub priority: Option<i32>,
+    pub pp: Option<i32>,
+    pub script: Option<String>,
+}
+
+fn default_category() -> String {
+    "anime".to_string()
+}
+
+impl SabnzbdConfig {
+    pub fn download_options(&self) -> DownloadOptions {
+        DownloadOptions {
+            category: Some(self.category.clone()),
+            priority: self.priority,
+            pp: self.pp,
+            script: self.script.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Brackets {
+    #[default]
+    Square,
+    Round,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityLocation {
+    /// the first bracket after the group
+    #[default]
+    First,
+    /// the last bracket in the title, for names that contain brackets themselves
+    Last,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GroupGrammar {
+    /// Which brackets hold the quality and other tags
+    pub brackets: Option<Brackets>,
+    pub quality: Option<QualityLocation>,
+    /// What separates the name from the episode, " - " by default
+    pub separator: Option<String>,
+    /// Tags to step over when looking for the quality, "VRV" and "WEB" by default
+    pub skip: Option<Vec<String>>,
+    /// Tried before anything else; needs `name` and `episode` captures and may also
+    /// capture `season`, `version`, `quality` and `extension`
+    pub regex: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
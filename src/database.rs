@@ -1,13 +1,23 @@
-use crate::models::Quality;
+use crate::models;
+use crate::models::{
+    AttributeFilter, DownloadOptions, EpisodeEntry, EpisodeKind, EpisodeRecord, Quality,
+    ShowStatus, ShowSummary,
+};
 use chrono::NaiveDateTime;
-use rusqlite::{params, Connection, Error as RusqliteError, OptionalExtension, Result};
+use rusqlite::{
+    params, Connection, Error as RusqliteError, OpenFlags, OptionalExtension, Result, Row,
+    Transaction,
+};
 use std::env;
+use std::fmt;
 use std::path::PathBuf;
 use wildmatch::WildMatch;
 
 #[derive(Debug)]
 pub enum Error {
     DbError(RusqliteError),
+    // the schema is this many migrations behind, and can't be migrated under --dry-run
+    PendingMigrations(usize),
 }
 
 impl From<RusqliteError> for Error {
@@ -18,82 +28,383 @@ impl From<RusqliteError> for Error {
 
 pub struct Database {
     conn: Connection,
+    dry_run: bool,
 }
 
 const MIGRATE_V1: &str = std::include_str!("../sql/migrate_00001.sql");
+const MIGRATE_V2: &str = std::include_str!("../sql/migrate_00002.sql");
+const MIGRATE_V3: &str = std::include_str!("../sql/migrate_00003.sql");
+const MIGRATE_V4: &str = std::include_str!("../sql/migrate_00004.sql");
+const MIGRATE_V5: &str = std::include_str!("../sql/migrate_00005.sql");
+const MIGRATE_V6: &str = std::include_str!("../sql/migrate_00006.sql");
+const MIGRATE_V7: &str = std::include_str!("../sql/migrate_00007.sql");
+const MIGRATE_V8: &str = std::include_str!("../sql/migrate_00008.sql");
+const MIGRATE_V9: &str = std::include_str!("../sql/migrate_00009.sql");
 
-pub fn connect() -> Result<Database, Error> {
+// the first migration predates `user_version` tracking and is safe to re-run
+const MIGRATIONS: &[&str] = &[
+    MIGRATE_V1, MIGRATE_V2, MIGRATE_V3, MIGRATE_V4, MIGRATE_V5, MIGRATE_V6, MIGRATE_V7,
+    MIGRATE_V8, MIGRATE_V9,
+];
+
+/// Opens the database and brings its schema up to date. Under `dry_run` the database is
+/// opened read-only instead, and a schema that needs migrating is an error.
+pub fn connect(dry_run: bool) -> Result<Database, Error> {
     let mut home_dir: PathBuf = env::var_os("HOME").map(PathBuf::from).unwrap();
     home_dir.push(".config");
     home_dir.push("tosho");
     home_dir.push("database.sqlite");
-    let conn = Connection::open(&home_dir)?;
-    for migration in MIGRATE_V1
-        .split(';')
-        .map(str::trim)
-        .filter(|s| !s.is_empty())
-    {
-        conn.execute(migration, [])?;
-    }
-    Ok(Database { conn })
+    let flags = if dry_run {
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    } else {
+        OpenFlags::default()
+    };
+    let mut conn = Connection::open_with_flags(&home_dir, flags)?;
+    let user_version: usize =
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if dry_run && user_version < MIGRATIONS.len() {
+        return Err(Error::PendingMigrations(MIGRATIONS.len() - user_version));
+    }
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(user_version) {
+        let trans = conn.transaction()?;
+        for statement in migration
+            .split(';')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            trans.execute(statement, [])?;
+        }
+        trans.pragma_update(None, "user_version", version + 1)?;
+        trans.commit()?;
+    }
+    Ok(Database { conn, dry_run })
+}
+
+fn insert_show_episodes(
+    trans: &Transaction,
+    show_id: i64,
+    episodes: &[EpisodeEntry],
+) -> Result<(), Error> {
+    for ep in episodes {
+        trans.execute(
+            r#"INSERT INTO episodes
+                         (show_id, season, episode, kind, part, version, crc32, link, grabbed,
+                          video_codec, bit_depth, source, audio_codec, subtitles, raw, uncensored)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9,
+                                 $10, $11, $12, $13, $14, $15, $16)
+                         ON CONFLICT (show_id, season, kind, episode, part, version)
+                         DO NOTHING"#,
+            params![
+                &show_id,
+                &ep.season,
+                &ep.episode,
+                &ep.kind,
+                &ep.part.unwrap_or(0),
+                &ep.version,
+                &ep.crc32,
+                &ep.link,
+                &ep.grabbed,
+                &ep.attributes.video_codec,
+                &ep.attributes.bit_depth,
+                &ep.attributes.source,
+                &ep.attributes.audio_codec,
+                &ep.attributes.subtitles.join(","),
+                &ep.attributes.raw,
+                &ep.attributes.uncensored
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn print_dry_run_episodes(episodes: &[EpisodeEntry]) {
+    for ep in episodes {
+        println!(
+            "[dry-run]   {} v{} link {:?} grabbed {}",
+            ep.season_label(),
+            ep.version,
+            ep.link,
+            ep.grabbed
+        );
+    }
+}
+
+// `part` is stored as 0 for episodes without a fractional part so it can be in the primary key
+fn part_from_sql(part: i32) -> Option<i32> {
+    if part == 0 {
+        None
+    } else {
+        Some(part)
+    }
+}
+
+fn episode_record(row: &Row) -> Result<EpisodeRecord, RusqliteError> {
+    Ok(EpisodeRecord {
+        show_id: row.get(0)?,
+        group: row.get(1)?,
+        name: row.get(2)?,
+        quality: row.get(3)?,
+        season: row.get(4)?,
+        episode: row.get(5)?,
+        kind: row.get(6)?,
+        part: part_from_sql(row.get(7)?),
+        version: row.get(8)?,
+        link: row.get(9)?,
+        grabbed: row.get(10)?,
+        grabbed_on: row.get(11)?,
+    })
 }
 
 impl Database {
-    pub fn get_last_pub_date(&self) -> Result<NaiveDateTime, Error> {
-        self.conn
-            .query_row("SELECT last_pub_date FROM tosho", [], |row| row.get(0))
-            .map_err(Error::DbError)
+    // under --dry-run, prints the write that would have happened and returns true so the
+    // caller skips it
+    fn skip_write(&self, action: fmt::Arguments) -> bool {
+        if self.dry_run {
+            println!("[dry-run] would {}", action);
+        }
+        self.dry_run
     }
 
-    pub fn set_last_pub_date(&self, pub_date: &NaiveDateTime) -> Result<(), Error> {
-        self.conn
-            .execute(r#"UPDATE tosho SET last_pub_date = $1"#, params![pub_date])?;
+    /// The newest pub date seen from a source by the last check. Sources that haven't been
+    /// checked yet start from where the single animetosho feed used to leave off.
+    pub fn get_last_pub_date(&self, source: &str) -> Result<NaiveDateTime, Error> {
+        let pub_date = self
+            .conn
+            .query_row(
+                "SELECT last_pub_date FROM source_pub_dates WHERE source = $1",
+                params![source],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match pub_date {
+            Some(pub_date) => Ok(pub_date),
+            None => self
+                .conn
+                .query_row("SELECT last_pub_date FROM tosho", [], |row| row.get(0))
+                .map_err(Error::DbError),
+        }
+    }
+
+    pub fn set_last_pub_date(&self, source: &str, pub_date: &NaiveDateTime) -> Result<(), Error> {
+        if self.skip_write(format_args!("set last pub date for {} to {}", source, pub_date)) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"INSERT INTO source_pub_dates (source, last_pub_date) VALUES ($1, $2)
+               ON CONFLICT (source) DO UPDATE SET last_pub_date = excluded.last_pub_date"#,
+            params![source, pub_date],
+        )?;
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add_show_and_episodes(
         &mut self,
         group: &str,
         name: &str,
         quality: &Option<Quality>,
-        episodes: &Vec<(i32, i32, i32, String, bool)>,
+        download: &DownloadOptions,
+        specials: bool,
+        merge_seasons: bool,
+        filter: &AttributeFilter,
+        episodes: &[EpisodeEntry],
     ) -> Result<(), Error> {
+        if self.skip_write(format_args!(
+            "add show [{}] {} [{:?}] with {:?} specials {} merge seasons {} {:?} and episodes",
+            group, name, quality, download, specials, merge_seasons, filter
+        )) {
+            print_dry_run_episodes(episodes);
+            return Ok(());
+        }
         let trans = self.conn.transaction()?;
-        let show_id: i64 = trans.query_row(r#"SELECT MAX(show_id) + 1 FROM shows"#, [], |row| {
-            row.get(0)
-        })?;
+        let show_id: i64 = trans.query_row(
+            r#"SELECT COALESCE(MAX(show_id), 0) + 1 FROM shows"#,
+            [],
+            |row| row.get(0),
+        )?;
         trans.execute(
             r#"INSERT INTO shows
-               (show_id, "group", name, quality)
-               VALUES ($1, $2, $3, $4)"#,
-            params![&show_id, &group, &name, &quality],
+               (show_id, "group", name, quality, category, priority, pp, script, specials,
+                require_attributes, reject_attributes, merge_seasons)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
+            params![
+                &show_id,
+                &group,
+                &name,
+                &quality,
+                &download.category,
+                &download.priority,
+                &download.pp,
+                &download.script,
+                &specials,
+                &filter.require.join(","),
+                &filter.reject.join(","),
+                &merge_seasons
+            ],
         )?;
-        for ep in episodes {
-            trans.execute(
-                r#"INSERT INTO episodes
-                             (show_id, season, episode, version, link, grabbed)
-                             VALUES ($1, $2, $3, $4, $5, $6)
-                             ON CONFLICT (show_id, season, episode, version)
-                             DO NOTHING"#,
-                params![&show_id, &ep.0, &ep.1, &ep.2, &ep.3, &ep.4],
-            )?;
+        insert_show_episodes(&trans, show_id, episodes)?;
+        trans.commit()?;
+        Ok(())
+    }
+
+    pub fn add_show_episodes(
+        &mut self,
+        show_id: i64,
+        episodes: &[EpisodeEntry],
+    ) -> Result<(), Error> {
+        if self.skip_write(format_args!("add episodes to show {}", show_id)) {
+            print_dry_run_episodes(episodes);
+            return Ok(());
         }
+        let trans = self.conn.transaction()?;
+        insert_show_episodes(&trans, show_id, episodes)?;
         trans.commit()?;
         Ok(())
     }
 
-    pub fn add_episodes(&mut self, episodes: &Vec<(i64, Option<i32>, i32, i32, String)>) -> Result<(), Error> {
+    pub fn update_show(
+        &self,
+        show_id: i64,
+        group: &str,
+        name: &str,
+        quality: &Option<Quality>,
+    ) -> Result<(), Error> {
+        if self.skip_write(format_args!(
+            "update show {} to [{}] {} [{:?}]", show_id, group, name, quality
+        )) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"UPDATE shows SET "group" = $1, name = $2, quality = $3
+               WHERE show_id = $4"#,
+            params![&group, &name, &quality, &show_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_show(
+        &self,
+        show_id: i64,
+    ) -> Result<Option<(String, String, Option<Quality>)>, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT "group", name, quality FROM shows WHERE show_id = $1"#,
+                params![&show_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(Error::DbError)
+    }
+
+    pub fn get_download_options(&self, show_id: i64) -> Result<DownloadOptions, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT category, priority, pp, script FROM shows WHERE show_id = $1"#,
+                params![&show_id],
+                |row| {
+                    Ok(DownloadOptions {
+                        category: row.get(0)?,
+                        priority: row.get(1)?,
+                        pp: row.get(2)?,
+                        script: row.get(3)?,
+                    })
+                },
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn set_download_options(
+        &self,
+        show_id: i64,
+        download: &DownloadOptions,
+    ) -> Result<(), Error> {
+        if self.skip_write(format_args!(
+            "set download options of show {} to {:?}", show_id, download
+        )) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"UPDATE shows SET category = $1, priority = $2, pp = $3, script = $4
+               WHERE show_id = $5"#,
+            params![
+                &download.category,
+                &download.priority,
+                &download.pp,
+                &download.script,
+                &show_id
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_latest_episode(&self, show_id: i64) -> Result<Option<(i32, i32)>, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT season, episode FROM episodes
+                   WHERE show_id = $1 AND kind = 'regular'
+                   ORDER BY season DESC, episode DESC
+                   LIMIT 1"#,
+                params![&show_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(Error::DbError)
+    }
+
+    // sources without nzbs like nyaa report episodes with an empty link, which mustn't replace
+    // a link another source already found
+    pub fn add_episodes(&mut self, episodes: &[(i64, EpisodeEntry)]) -> Result<(), Error> {
+        if self.skip_write(format_args!("upsert {} episodes", episodes.len())) {
+            for (show_id, ep) in episodes {
+                println!(
+                    "[dry-run]   show {} {} v{} link {:?}",
+                    show_id,
+                    ep.season_label(),
+                    ep.version,
+                    ep.link
+                );
+            }
+            return Ok(());
+        }
         let trans = self.conn.transaction()?;
-        for (show_id, season, ep, version, link) in episodes {
-            let season = season.unwrap_or(1);
+        for (show_id, ep) in episodes {
             trans.execute(
                 r#"INSERT INTO episodes
-                             (show_id, season, episode, version, link, grabbed)
-                             VALUES ($1, $2, $3, $4, $5, FALSE)
-                             ON CONFLICT (show_id, season, episode, version)
+                             (show_id, season, episode, kind, part, version, crc32, link, grabbed,
+                              video_codec, bit_depth, source, audio_codec, subtitles, raw,
+                              uncensored)
+                             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9,
+                                     $10, $11, $12, $13, $14, $15, $16)
+                             ON CONFLICT (show_id, season, kind, episode, part, version)
                              DO UPDATE
-                             SET link = EXCLUDED.link"#,
-                params![&show_id, &season, &ep, &version, &link],
+                             SET link = CASE WHEN EXCLUDED.link = '' THEN episodes.link
+                                             ELSE EXCLUDED.link END,
+                                 crc32 = EXCLUDED.crc32,
+                                 video_codec = EXCLUDED.video_codec,
+                                 bit_depth = EXCLUDED.bit_depth,
+                                 source = EXCLUDED.source,
+                                 audio_codec = EXCLUDED.audio_codec,
+                                 subtitles = EXCLUDED.subtitles,
+                                 raw = EXCLUDED.raw,
+                                 uncensored = EXCLUDED.uncensored"#,
+                params![
+                    &show_id,
+                    &ep.season,
+                    &ep.episode,
+                    &ep.kind,
+                    &ep.part.unwrap_or(0),
+                    &ep.version,
+                    &ep.crc32,
+                    &ep.link,
+                    &ep.grabbed,
+                    &ep.attributes.video_codec,
+                    &ep.attributes.bit_depth,
+                    &ep.attributes.source,
+                    &ep.attributes.audio_codec,
+                    &ep.attributes.subtitles.join(","),
+                    &ep.attributes.raw,
+                    &ep.attributes.uncensored
+                ],
             )?;
         }
         trans.commit()?;
@@ -108,7 +419,9 @@ impl Database {
     ) -> Result<Option<i64>, Error> {
         let mut stmt = self.conn.prepare(
             r#"SELECT show_id, "group", quality FROM shows WHERE
-               LOWER(name) = LOWER($1)"#,
+               (LOWER(name) = LOWER($1)
+                OR show_id IN (SELECT show_id FROM show_aliases WHERE LOWER(alias) = LOWER($1)))
+               AND status != 'archived'"#,
         )?;
         let show_iter = stmt.query_map(params![&name], |row| {
             let show_id: i64 = row.get(0)?;
@@ -138,8 +451,7 @@ impl Database {
     pub fn get_episode(
         &self,
         show_id: &i64,
-        episode: &i32,
-        version: &i32,
+        ep: &EpisodeEntry,
     ) -> Result<Option<(i64, String, String, Option<Quality>, i32, i32)>, Error> {
         self.conn
             .query_row(
@@ -147,8 +459,16 @@ impl Database {
                FROM episodes e
                JOIN shows s
                ON e.show_id = s.show_id
-               WHERE s.show_id = $1 AND e.episode = $2 AND e.version = $3"#,
-                params![show_id, episode, version],
+               WHERE s.show_id = $1 AND e.season = $2 AND e.kind = $3
+               AND e.episode = $4 AND e.part = $5 AND e.version = $6"#,
+                params![
+                    show_id,
+                    &ep.season,
+                    &ep.kind,
+                    &ep.episode,
+                    &ep.part.unwrap_or(0),
+                    &ep.version
+                ],
                 |row| {
                     Ok((
                         row.get(0)?,
@@ -164,46 +484,403 @@ impl Database {
             .map_err(Error::DbError)
     }
 
-    pub fn list_episodes_missing_nzb(
-        &self,
-    ) -> Result<Vec<(i64, String, String, Option<Quality>, i32, i32)>, Error> {
+    pub fn get_episode_crc32(&self, show_id: i64, ep: &EpisodeEntry) -> Result<Option<u32>, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT crc32 FROM episodes
+                   WHERE show_id = $1 AND season = $2 AND kind = $3
+                   AND episode = $4 AND part = $5 AND version = $6"#,
+                params![
+                    &show_id,
+                    &ep.season,
+                    &ep.kind,
+                    &ep.episode,
+                    &ep.part.unwrap_or(0),
+                    &ep.version
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(Error::DbError)
+    }
+
+    pub fn list_episodes_missing_nzb(&self) -> Result<Vec<EpisodeRecord>, Error> {
         let mut stmt = self.conn.prepare(
-            r#"SELECT e.show_id, s.name, s."group", s.quality, e.episode, e.version
+            r#"SELECT e.show_id, s."group", s.name, s.quality,
+                      e.season, e.episode, e.kind, e.part, e.version,
+                      e.link, e.grabbed, e.grabbed_on
                FROM episodes e
                JOIN shows s
                ON e.show_id = s.show_id
-               WHERE e.link = ''"#,
+               WHERE e.link = '' AND e.grabbed IS FALSE AND s.status != 'archived'"#,
+        )?;
+        let rows = stmt.query_map([], episode_record)?;
+        rows.collect::<Result<_, RusqliteError>>()
+            .map_err(Error::DbError)
+    }
+
+    pub fn list_ungrapped_nzbs(&self) -> Result<Vec<(i64, String, DownloadOptions)>, Error> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT DISTINCT e.show_id, e.link, s.category, s.priority, s.pp, s.script
+               FROM episodes e
+               JOIN shows s
+               ON e.show_id = s.show_id
+               WHERE e.grabbed IS FALSE AND s.status = 'active'"#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                DownloadOptions {
+                    category: row.get(2)?,
+                    priority: row.get(3)?,
+                    pp: row.get(4)?,
+                    script: row.get(5)?,
+                },
+            ))
+        })?;
+        rows.collect::<Result<_, RusqliteError>>()
+            .map_err(Error::DbError)
+    }
+
+    fn count_episodes(
+        &self,
+        show_id: i64,
+        season: Option<i32>,
+        kind: EpisodeKind,
+        episode: i32,
+        part: Option<i32>,
+        version: Option<i32>,
+    ) -> Result<usize, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT COUNT(*) FROM episodes
+                   WHERE show_id = $1 AND ($2 IS NULL OR season = $2) AND kind = $3
+                   AND episode = $4 AND part = $5 AND ($6 IS NULL OR version = $6)"#,
+                params![&show_id, &season, &kind, &episode, &part.unwrap_or(0), &version],
+                |row| row.get(0),
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn mark_grabbed(
+        &self,
+        show_id: i64,
+        season: Option<i32>,
+        kind: EpisodeKind,
+        episode: i32,
+        part: Option<i32>,
+        version: Option<i32>,
+    ) -> Result<usize, Error> {
+        if self.skip_write(format_args!(
+            "mark {} {} (season {:?}, version {:?}) of show {} as grabbed",
+            kind,
+            models::episode_label(kind, episode, part),
+            season,
+            version,
+            show_id
+        )) {
+            return self.count_episodes(show_id, season, kind, episode, part, version);
+        }
+        self.conn
+            .execute(
+                r#"UPDATE episodes SET
+                   grabbed = TRUE,
+                   grabbed_on = (datetime('now'))
+                   WHERE show_id = $1 AND ($2 IS NULL OR season = $2) AND kind = $3
+                   AND episode = $4 AND part = $5 AND ($6 IS NULL OR version = $6)"#,
+                params![&show_id, &season, &kind, &episode, &part.unwrap_or(0), &version],
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn mark_ungrabbed(
+        &self,
+        show_id: i64,
+        season: Option<i32>,
+        kind: EpisodeKind,
+        episode: i32,
+        part: Option<i32>,
+        version: Option<i32>,
+    ) -> Result<usize, Error> {
+        if self.skip_write(format_args!(
+            "mark {} {} (season {:?}, version {:?}) of show {} as not grabbed",
+            kind,
+            models::episode_label(kind, episode, part),
+            season,
+            version,
+            show_id
+        )) {
+            return self.count_episodes(show_id, season, kind, episode, part, version);
+        }
+        self.conn
+            .execute(
+                r#"UPDATE episodes SET
+                   grabbed = FALSE,
+                   grabbed_on = NULL
+                   WHERE show_id = $1 AND ($2 IS NULL OR season = $2) AND kind = $3
+                   AND episode = $4 AND part = $5 AND ($6 IS NULL OR version = $6)"#,
+                params![&show_id, &season, &kind, &episode, &part.unwrap_or(0), &version],
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn mark_link_grabbed(&self, show_id: i64, link: &str) -> Result<(), Error> {
+        if self.skip_write(format_args!(
+            "mark episodes of show {} with link {} as grabbed", show_id, link
+        )) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"UPDATE episodes SET
+               grabbed = TRUE,
+               grabbed_on = (datetime('now'))
+               WHERE show_id = $1 AND link = $2 AND grabbed IS FALSE"#,
+            params![&show_id, &link],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_shows(&self) -> Result<Vec<ShowSummary>, Error> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.show_id, s."group", s.name, s.quality,
+                      l.season, l.episode, l.version,
+                      COALESCE(c.grabbed, 0), COALESCE(c.ungrabbed, 0), c.last_grabbed_on,
+                      s.status
+               FROM shows s
+               LEFT JOIN (SELECT show_id, season, episode, version,
+                                 ROW_NUMBER() OVER (
+                                     PARTITION BY show_id
+                                     ORDER BY season DESC, episode DESC, version DESC
+                                 ) AS rn
+                          FROM episodes
+                          WHERE kind = 'regular') l
+               ON l.show_id = s.show_id AND l.rn = 1
+               LEFT JOIN (SELECT show_id,
+                                 SUM(grabbed IS TRUE) AS grabbed,
+                                 SUM(grabbed IS FALSE) AS ungrabbed,
+                                 MAX(grabbed_on) AS last_grabbed_on
+                          FROM episodes
+                          GROUP BY show_id) c
+               ON c.show_id = s.show_id
+               ORDER BY LOWER(s.name), LOWER(s."group"), s.show_id"#,
         )?;
         let rows = stmt.query_map([], |row| {
+            Ok(ShowSummary {
+                show_id: row.get(0)?,
+                group: row.get(1)?,
+                name: row.get(2)?,
+                quality: row.get(3)?,
+                latest_season: row.get(4)?,
+                latest_episode: row.get(5)?,
+                latest_version: row.get(6)?,
+                grabbed: row.get(7)?,
+                ungrabbed: row.get(8)?,
+                last_grabbed_on: row.get(9)?,
+                status: row.get(10)?,
+            })
+        })?;
+        rows.collect::<Result<_, RusqliteError>>()
+            .map_err(Error::DbError)
+    }
+
+    pub fn find_shows(
+        &self,
+        name: &str,
+    ) -> Result<Vec<(i64, String, String, Option<Quality>, ShowStatus)>, Error> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT show_id, "group", name, quality, status FROM shows
+               WHERE LOWER(name) = LOWER($1)
+                  OR show_id IN (SELECT show_id FROM show_aliases WHERE LOWER(alias) = LOWER($1))
+               ORDER BY show_id"#,
+        )?;
+        let rows = stmt.query_map(params![&name], |row| {
             Ok((
                 row.get(0)?,
                 row.get(1)?,
                 row.get(2)?,
                 row.get(3)?,
                 row.get(4)?,
-                row.get(5)?,
             ))
         })?;
         rows.collect::<Result<_, RusqliteError>>()
             .map_err(Error::DbError)
     }
 
-    pub fn list_ungrapped_nzbs(&self) -> Result<Vec<(i64, i32, String)>, Error> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT show_id, episode, link FROM episodes WHERE grabbed IS FALSE")?;
-        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    pub fn remove_show(&mut self, show_id: i64) -> Result<(), Error> {
+        if self.skip_write(format_args!("remove show {} with its episodes and aliases", show_id)) {
+            return Ok(());
+        }
+        let trans = self.conn.transaction()?;
+        trans.execute(
+            r#"DELETE FROM episodes WHERE show_id = $1"#,
+            params![&show_id],
+        )?;
+        trans.execute(
+            r#"DELETE FROM show_aliases WHERE show_id = $1"#,
+            params![&show_id],
+        )?;
+        trans.execute(r#"DELETE FROM shows WHERE show_id = $1"#, params![&show_id])?;
+        trans.commit()?;
+        Ok(())
+    }
+
+    pub fn set_status(&self, show_id: i64, status: ShowStatus) -> Result<(), Error> {
+        if self.skip_write(format_args!("set status of show {} to {}", show_id, status)) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"UPDATE shows SET status = $1 WHERE show_id = $2"#,
+            params![&status, &show_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_alias(&self, show_id: i64, alias: &str) -> Result<(), Error> {
+        if self.skip_write(format_args!("add alias {:?} to show {}", alias, show_id)) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"INSERT INTO show_aliases (show_id, alias)
+               VALUES ($1, $2)
+               ON CONFLICT (show_id, alias)
+               DO NOTHING"#,
+            params![&show_id, &alias],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_alias(&self, show_id: i64, alias: &str) -> Result<usize, Error> {
+        if self.skip_write(format_args!("remove alias {:?} from show {}", alias, show_id)) {
+            return self
+                .conn
+                .query_row(
+                    r#"SELECT COUNT(*) FROM show_aliases
+                       WHERE show_id = $1 AND LOWER(alias) = LOWER($2)"#,
+                    params![&show_id, &alias],
+                    |row| row.get(0),
+                )
+                .map_err(Error::DbError);
+        }
+        self.conn
+            .execute(
+                r#"DELETE FROM show_aliases
+                   WHERE show_id = $1 AND LOWER(alias) = LOWER($2)"#,
+                params![&show_id, &alias],
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn get_aliases(&self, show_id: i64) -> Result<Vec<String>, Error> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT alias FROM show_aliases WHERE show_id = $1 ORDER BY alias"#,
+        )?;
+        let rows = stmt.query_map(params![&show_id], |row| row.get(0))?;
+        rows.collect::<Result<_, RusqliteError>>()
+            .map_err(Error::DbError)
+    }
+
+    pub fn list_aliases(&self) -> Result<Vec<(i64, String, String, String)>, Error> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.show_id, s."group", s.name, a.alias
+               FROM show_aliases a
+               JOIN shows s
+               ON a.show_id = s.show_id
+               ORDER BY LOWER(s.name), s.show_id, a.alias"#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
         rows.collect::<Result<_, RusqliteError>>()
             .map_err(Error::DbError)
     }
 
-    pub fn mark_grabbed(&self, show_id: i64, episode: i32) -> Result<(), Error> {
+    pub fn list_history(&self, since: Option<&NaiveDateTime>) -> Result<Vec<EpisodeRecord>, Error> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT e.show_id, s."group", s.name, s.quality,
+                      e.season, e.episode, e.kind, e.part, e.version,
+                      e.link, e.grabbed, e.grabbed_on
+               FROM episodes e
+               JOIN shows s
+               ON e.show_id = s.show_id
+               WHERE $1 IS NULL OR e.grabbed_on >= $1
+               ORDER BY e.grabbed_on DESC, LOWER(s.name), e.season DESC, e.episode DESC, e.version DESC"#,
+        )?;
+        let rows = stmt.query_map(params![&since], episode_record)?;
+        rows.collect::<Result<_, RusqliteError>>()
+            .map_err(Error::DbError)
+    }
+
+    pub fn get_specials(&self, show_id: i64) -> Result<bool, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT specials FROM shows WHERE show_id = $1"#,
+                params![&show_id],
+                |row| row.get(0),
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn get_merge_seasons(&self, show_id: i64) -> Result<bool, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT merge_seasons FROM shows WHERE show_id = $1"#,
+                params![&show_id],
+                |row| row.get(0),
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn set_merge_seasons(&self, show_id: i64, merge_seasons: bool) -> Result<(), Error> {
+        if self.skip_write(format_args!(
+            "set merge seasons of show {} to {}", show_id, merge_seasons
+        )) {
+            return Ok(());
+        }
         self.conn.execute(
-            r#"UPDATE episodes SET
-               grabbed = TRUE,
-               grabbed_on = (datetime('now'))
-               WHERE show_id = $1 AND episode = $2"#,
-            params![&show_id, &episode],
+            r#"UPDATE shows SET merge_seasons = $1 WHERE show_id = $2"#,
+            params![&merge_seasons, &show_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_attribute_filter(&self, show_id: i64) -> Result<AttributeFilter, Error> {
+        self.conn
+            .query_row(
+                r#"SELECT require_attributes, reject_attributes FROM shows WHERE show_id = $1"#,
+                params![&show_id],
+                |row| {
+                    Ok(AttributeFilter {
+                        require: models::parse_tags(&row.get::<_, String>(0)?),
+                        reject: models::parse_tags(&row.get::<_, String>(1)?),
+                    })
+                },
+            )
+            .map_err(Error::DbError)
+    }
+
+    pub fn set_attribute_filter(&self, show_id: i64, filter: &AttributeFilter) -> Result<(), Error> {
+        if self.skip_write(format_args!(
+            "set attribute filter of show {} to {:?}", show_id, filter
+        )) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"UPDATE shows SET require_attributes = $1, reject_attributes = $2
+               WHERE show_id = $3"#,
+            params![&filter.require.join(","), &filter.reject.join(","), &show_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_specials(&self, show_id: i64, specials: bool) -> Result<(), Error> {
+        if self.skip_write(format_args!("set specials of show {} to {}", show_id, specials)) {
+            return Ok(());
+        }
+        self.conn.execute(
+            r#"UPDATE shows SET specials = $1 WHERE show_id = $2"#,
+            params![&specials, &show_id],
         )?;
         Ok(())
     }
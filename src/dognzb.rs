@@ -118,7 +118,7 @@ impl Item {
 }
 
 pub fn get_bookmarks(apikey: &str) -> Result<Vec<Item>, Error> {
-    let response = curl::get(&format!("https://dognzb.cr/rss.cfm?r={apikey}&t=9000"))?;
+    let response = curl::get(format!("https://dognzb.cr/rss.cfm?r={apikey}&t=9000"))?;
     let links = read_from(&response.body[..])?;
     Ok(links)
 }
@@ -1,4 +1,6 @@
-#![allow(clippy::enum_variant_names, clippy::type_complexity)]
+// error payloads are only read through Debug when they're printed, and the feed parsers keep
+// fields that no command reads yet
+#![allow(dead_code, clippy::enum_variant_names, clippy::type_complexity)]
 use gumdrop::Options;
 
 mod commands;
@@ -7,45 +9,275 @@ mod curl;
 mod database;
 mod dognzb;
 mod models;
+mod newznab;
+mod nyaa;
 mod options;
 mod rss;
 mod sabnzbd;
+mod sources;
+mod torznab;
 mod tosho;
 mod utils;
 
+fn strip_brackets(group: &str) -> &str {
+    if group.starts_with('[') {
+        &group[1..group.len() - 1]
+    } else {
+        group
+    }
+}
+
 fn main() {
     let config = config::Config::load();
-
-    let mut db = database::connect().unwrap();
-    let sabnzbd = sabnzbd::SabnzbdClient::new(&config.sabnzbd.url, &config.sabnzbd.apikey);
+    if let Err(err) = utils::set_group_grammars(&config.groups) {
+        eprintln!("Bad group regex in tosho.toml: {}", err);
+        std::process::exit(1);
+    }
 
     let opts = options::ToshoOptions::parse_args_default_or_exit();
 
+    let mut db = match database::connect(opts.dry_run) {
+        Ok(db) => db,
+        Err(database::Error::PendingMigrations(_)) => {
+            eprintln!("The database needs migrating, run tosho once without --dry-run first");
+            std::process::exit(1);
+        }
+        Err(err) => panic!("{:?}", err),
+    };
+    let mut sabnzbd = sabnzbd::SabnzbdClient::new(&config.sabnzbd.url, &config.sabnzbd.apikey);
+    sabnzbd.set_dry_run(opts.dry_run);
+
+    // everything but search records nzb links for queue, so skips torrent-only sources
+    let search_sources = sources::from_config(&config.sources, false);
+    let search_fallbacks = sources::from_config(&config.sources, true);
+    let sources = sources::with_nzbs(sources::from_config(&config.sources, false));
+    let fallbacks = sources::with_nzbs(sources::from_config(&config.sources, true));
+
     match opts.command {
         Some(options::Command::Add(opts)) => {
-            let group = if opts.group.starts_with('[') {
-                &opts.group[1..opts.group.len() - 1]
-            } else {
-                &opts.group
-            };
+            let group = strip_brackets(&opts.group);
             let start_season = opts.season.unwrap_or(1);
             let start_ep = opts.start.unwrap_or(1);
-            commands::add(&mut db, group, &opts.show, start_season, start_ep, &opts.quality)
-                .unwrap_or_else(|e| e.exit());
+            let download = models::DownloadOptions {
+                category: opts.category,
+                priority: opts.priority,
+                pp: opts.pp,
+                script: opts.script,
+            };
+            commands::add(
+                &mut db,
+                &sources,
+                group,
+                &opts.show,
+                start_season,
+                start_ep,
+                &opts.quality,
+                &download,
+                opts.specials,
+                opts.merge_seasons,
+                &models::AttributeFilter {
+                    require: opts.require.as_deref().map(models::parse_tags).unwrap_or_default(),
+                    reject: opts.reject.as_deref().map(models::parse_tags).unwrap_or_default(),
+                },
+            )
+            .unwrap_or_else(|e| e.exit());
         }
         Some(options::Command::Queue(_)) => {
-            commands::queue(&db, &sabnzbd).unwrap_or_else(|e| e.exit());
+            commands::queue(&db, &sabnzbd, &config.sabnzbd.download_options())
+                .unwrap_or_else(|e| e.exit());
         }
         Some(options::Command::Check(_)) => {
-            commands::check_missing(&mut db).unwrap_or_else(|e| e.exit());
-            commands::check(&mut db).unwrap_or_else(|e| e.exit());
+            commands::check_missing(&mut db, &sources, &fallbacks).unwrap_or_else(|e| e.exit());
+            commands::check(&mut db, &sources).unwrap_or_else(|e| e.exit());
         }
         Some(options::Command::Recheck(opts)) => {
-            commands::recheck(&mut db, opts.page.unwrap_or(1)).unwrap_or_else(|e| e.exit());
+            commands::recheck(&mut db, &sources, opts.page.unwrap_or(1)).unwrap_or_else(|e| e.exit());
         }
         Some(options::Command::Dog(_)) => {
             commands::dog(&config.dognzb.apikey, &sabnzbd).unwrap_or_else(|e| e.exit());
         }
+        Some(options::Command::List(opts)) => {
+            commands::list(
+                &db,
+                opts.group.as_deref(),
+                opts.name.as_deref(),
+                opts.ungrabbed_only,
+            )
+            .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Remove(opts)) => {
+            let group = opts.group.as_deref().map(strip_brackets);
+            commands::remove(&mut db, &opts.show, group, &opts.quality)
+                .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Archive(opts)) => {
+            let group = opts.group.as_deref().map(strip_brackets);
+            commands::archive(&db, &opts.show, group, &opts.quality, opts.undo)
+                .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Edit(opts)) => {
+            if !opts.backfill && (opts.season.is_some() || opts.start.is_some()) {
+                eprintln!("--season and --start only apply with --backfill");
+                std::process::exit(1);
+            }
+            let group = opts.group.as_deref().map(strip_brackets);
+            let new_group = opts.new_group.as_deref().map(strip_brackets);
+            let new_quality = if opts.no_quality {
+                Some(None)
+            } else {
+                opts.new_quality.map(Some)
+            };
+            let show_id = commands::edit(
+                &db,
+                &opts.show,
+                group,
+                &opts.quality,
+                new_group,
+                opts.new_name.as_deref(),
+                new_quality,
+            )
+            .unwrap_or_else(|e| e.exit());
+            let download = models::DownloadOptions {
+                category: opts.category,
+                priority: opts.priority,
+                pp: opts.pp,
+                script: opts.script,
+            };
+            if opts.reset_download_options || download != models::DownloadOptions::default() {
+                commands::set_download_options(&db, show_id, download, opts.reset_download_options)
+                    .unwrap_or_else(|e| e.exit());
+            }
+            if opts.specials || opts.no_specials {
+                commands::set_specials(&db, show_id, opts.specials).unwrap_or_else(|e| e.exit());
+            }
+            if opts.merge_seasons || opts.no_merge_seasons {
+                commands::set_merge_seasons(&db, show_id, opts.merge_seasons)
+                    .unwrap_or_else(|e| e.exit());
+            }
+            if opts.require.is_some() || opts.reject.is_some() {
+                commands::set_attribute_filter(
+                    &db,
+                    show_id,
+                    opts.require.as_deref(),
+                    opts.reject.as_deref(),
+                )
+                .unwrap_or_else(|e| e.exit());
+            }
+            if opts.backfill {
+                commands::backfill(&mut db, &sources, show_id, opts.season, opts.start)
+                    .unwrap_or_else(|e| e.exit());
+            }
+        }
+        Some(options::Command::Pause(opts)) => {
+            let group = opts.group.as_deref().map(strip_brackets);
+            commands::set_status(&db, &opts.show, group, &opts.quality, models::ShowStatus::Paused)
+                .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Resume(opts)) => {
+            let group = opts.group.as_deref().map(strip_brackets);
+            commands::set_status(&db, &opts.show, group, &opts.quality, models::ShowStatus::Active)
+                .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Alias(opts)) => match opts.command {
+            Some(options::AliasCommand::Add(opts)) => {
+                let group = opts.group.as_deref().map(strip_brackets);
+                commands::add_alias(&db, &opts.show, group, &opts.quality, &opts.alias)
+                    .unwrap_or_else(|e| e.exit());
+            }
+            Some(options::AliasCommand::Remove(opts)) => {
+                let group = opts.group.as_deref().map(strip_brackets);
+                commands::remove_alias(&db, &opts.show, group, &opts.quality, &opts.alias)
+                    .unwrap_or_else(|e| e.exit());
+            }
+            Some(options::AliasCommand::List(opts)) => {
+                commands::list_aliases(&db, opts.show.as_deref()).unwrap_or_else(|e| e.exit());
+            }
+            None => {
+                unreachable!();
+            }
+        },
+        Some(options::Command::Search(opts)) => {
+            let results =
+                commands::search(
+                    &search_sources,
+                    &search_fallbacks,
+                    &opts.terms.join(" "),
+                    opts.pages.unwrap_or(1),
+                )
+                .unwrap_or_else(|e| e.exit());
+            if let Some(index) = opts.add {
+                let download = models::DownloadOptions {
+                    category: opts.category,
+                    priority: opts.priority,
+                    pp: opts.pp,
+                    script: opts.script,
+                };
+                commands::add_search_result(
+                    &mut db,
+                    &sources,
+                    &results,
+                    index,
+                    opts.season.unwrap_or(1),
+                    opts.start.unwrap_or(1),
+                    &download,
+                    opts.specials,
+                    opts.merge_seasons,
+                    &models::AttributeFilter {
+                        require: opts.require.as_deref().map(models::parse_tags).unwrap_or_default(),
+                        reject: opts.reject.as_deref().map(models::parse_tags).unwrap_or_default(),
+                    },
+                )
+                .unwrap_or_else(|e| e.exit());
+            }
+        }
+        Some(options::Command::History(opts)) => {
+            commands::history(&db, opts.show.as_deref(), opts.since.as_deref())
+                .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Mark(opts)) => {
+            if opts.grabbed == opts.ungrabbed {
+                eprintln!("Specify exactly one of --grabbed or --ungrabbed");
+                std::process::exit(1);
+            }
+            if opts.record && !opts.grabbed {
+                eprintln!("--record only applies with --grabbed");
+                std::process::exit(1);
+            }
+            let part = opts.part.as_deref().map(|part| {
+                utils::parse_part(part).unwrap_or_else(|| {
+                    eprintln!("--part takes one or two digits, e.g. 5 for 12.5");
+                    std::process::exit(1);
+                })
+            });
+            // fractional episodes are parsed as specials
+            let kind = match (opts.kind, part) {
+                (Some(kind), _) => kind,
+                (None, Some(_)) => models::EpisodeKind::Special,
+                (None, None) => models::EpisodeKind::Regular,
+            };
+            let group = opts.group.as_deref().map(strip_brackets);
+            commands::mark(
+                &mut db,
+                &opts.show,
+                group,
+                &opts.quality,
+                &opts.episodes,
+                opts.season,
+                kind,
+                part,
+                opts.version,
+                opts.grabbed,
+                opts.record,
+            )
+            .unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Verify(opts)) => {
+            commands::verify(&db, &opts.files, opts.requeue).unwrap_or_else(|e| e.exit());
+        }
+        Some(options::Command::Parse(opts)) => match opts.corpus {
+            Some(corpus) => commands::replay_corpus(&corpus).unwrap_or_else(|e| e.exit()),
+            None => commands::parse(&opts.titles).unwrap_or_else(|e| e.exit()),
+        },
         None => {
             unreachable!();
         }
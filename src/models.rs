@@ -1,22 +1,42 @@
+use chrono::NaiveDateTime;
 use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
 use rusqlite::Error as RusqliteError;
+use serde::{Serialize, Serializer};
 use std::convert::From;
 use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
-use std::string::ToString;
 
+// variants are declared from lowest to highest resolution, which is what `Ord` compares by
 #[allow(non_camel_case_types)]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Quality {
     Low_480p,
+    Low_540p,
+    Low_576p,
     Mid_720p,
     HD_1080p,
+    UHD_2160p,
+}
+
+impl Quality {
+    fn from_height(height: &str) -> Result<Quality, BadQuality> {
+        match height {
+            "480" => Ok(Quality::Low_480p),
+            "540" => Ok(Quality::Low_540p),
+            "576" => Ok(Quality::Low_576p),
+            "720" => Ok(Quality::Mid_720p),
+            "1080" => Ok(Quality::HD_1080p),
+            "2160" => Ok(Quality::UHD_2160p),
+            _ => Err(BadQuality),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct BadQuality;
-impl std::fmt::Display for BadQuality {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl fmt::Display for BadQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
@@ -28,23 +48,41 @@ impl FromStr for Quality {
     fn from_str(s: &str) -> Result<Quality, BadQuality> {
         match s {
             "480p" | "480" | "LOW" | "low" | "Low" | "LQ" | "Lq" | "lq" => Ok(Quality::Low_480p),
+            "540p" | "540" => Ok(Quality::Low_540p),
+            "576p" | "576" => Ok(Quality::Low_576p),
             "720p" | "720" | "MID" | "mid" | "Mid" => Ok(Quality::Mid_720p),
             "1080p" | "1080" | "HD" | "hd" | "Hd" => Ok(Quality::HD_1080p),
-            _ => Err(BadQuality),
+            "2160p" | "2160" | "4K" | "4k" | "UHD" | "uhd" | "Uhd" => Ok(Quality::UHD_2160p),
+            // explicit resolutions like "1920x1080"
+            _ => match s.split_once(['x', 'X']) {
+                Some((width, height)) if width.parse::<u32>().is_ok() => {
+                    Quality::from_height(height)
+                }
+                _ => Err(BadQuality),
+            },
         }
     }
 }
 
-impl ToString for Quality {
-    fn to_string(&self) -> String {
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Quality::Low_480p => String::from("480p"),
-            Quality::Mid_720p => String::from("720p"),
-            Quality::HD_1080p => String::from("1080p"),
+            Quality::Low_480p => write!(f, "480p"),
+            Quality::Low_540p => write!(f, "540p"),
+            Quality::Low_576p => write!(f, "576p"),
+            Quality::Mid_720p => write!(f, "720p"),
+            Quality::HD_1080p => write!(f, "1080p"),
+            Quality::UHD_2160p => write!(f, "2160p"),
         }
     }
 }
 
+impl Serialize for Quality {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl FromSql for Quality {
     fn column_result(value: ValueRef) -> FromSqlResult<Self> {
         value.as_str().and_then(|s| match Quality::from_str(s) {
@@ -55,18 +93,405 @@ impl FromSql for Quality {
 }
 
 impl ToSql for Quality {
-    fn to_sql(&self) -> Result<ToSqlOutput, RusqliteError> {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, RusqliteError> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowStatus {
+    Active,
+    /// Still tracked, but nothing is queued until it's resumed
+    Paused,
+    /// No longer tracked, with its episodes kept for the history
+    Archived,
+}
+
+#[derive(Debug)]
+pub struct BadShowStatus;
+impl fmt::Display for BadShowStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl Error for BadShowStatus {}
+
+impl FromStr for ShowStatus {
+    type Err = BadShowStatus;
+
+    fn from_str(s: &str) -> Result<ShowStatus, BadShowStatus> {
+        match s {
+            "active" => Ok(ShowStatus::Active),
+            "paused" => Ok(ShowStatus::Paused),
+            "archived" => Ok(ShowStatus::Archived),
+            _ => Err(BadShowStatus),
+        }
+    }
+}
+
+impl fmt::Display for ShowStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShowStatus::Active => write!(f, "active"),
+            ShowStatus::Paused => write!(f, "paused"),
+            ShowStatus::Archived => write!(f, "archived"),
+        }
+    }
+}
+
+impl FromSql for ShowStatus {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        value.as_str().and_then(|s| match ShowStatus::from_str(s) {
+            Ok(status) => Ok(status),
+            Err(e) => Err(FromSqlError::Other(Box::new(e))),
+        })
+    }
+}
+
+impl ToSql for ShowStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, RusqliteError> {
         Ok(ToSqlOutput::from(self.to_string()))
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadOptions {
+    pub category: Option<String>,
+    pub priority: Option<i32>,
+    pub pp: Option<i32>,
+    pub script: Option<String>,
+}
+
+impl DownloadOptions {
+    pub fn or(self, defaults: &DownloadOptions) -> DownloadOptions {
+        DownloadOptions {
+            category: self.category.or_else(|| defaults.category.clone()),
+            priority: self.priority.or(defaults.priority),
+            pp: self.pp.or(defaults.pp),
+            script: self.script.or_else(|| defaults.script.clone()),
+        }
+    }
+}
+
+/// Release details from the tags in a title, e.g. "[HEVC x265 10bit][Eng-Subs]".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReleaseAttributes {
+    /// "HEVC", "AVC" or "AV1"
+    pub video_codec: Option<String>,
+    pub bit_depth: Option<i32>,
+    /// "WEB", "BD", "DVD" or "TV"
+    pub source: Option<String>,
+    /// "AAC", "DDP", "AC3", "FLAC", "Opus", "DTS" or "MP3"
+    pub audio_codec: Option<String>,
+    /// Subtitle languages like "Eng", or "Multi" for multiple
+    pub subtitles: Vec<String>,
+    pub raw: bool,
+    pub uncensored: bool,
+}
+
+impl ReleaseAttributes {
+    /// The attributes as lowercase tags like "hevc", "10bit", "web" or "eng-subs",
+    /// which is what show filters are written in.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        tags.extend(self.video_codec.as_ref().map(|codec| codec.to_lowercase()));
+        tags.extend(self.bit_depth.map(|depth| format!("{}bit", depth)));
+        tags.extend(self.source.as_ref().map(|source| source.to_lowercase()));
+        tags.extend(self.audio_codec.as_ref().map(|codec| codec.to_lowercase()));
+        tags.extend(
+            self.subtitles
+                .iter()
+                .map(|lang| format!("{}-subs", lang.to_lowercase())),
+        );
+        if self.raw {
+            tags.push("raw".to_string());
+        }
+        if self.uncensored {
+            tags.push("uncensored".to_string());
+        }
+        tags
+    }
+}
+
+/// The release attributes a show's episodes must have, and those they must not.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeFilter {
+    pub require: Vec<String>,
+    pub reject: Vec<String>,
+}
+
+/// Splits a comma separated list of attribute tags like "hevc,10bit".
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+impl AttributeFilter {
+    pub fn allows(&self, attributes: &ReleaseAttributes) -> bool {
+        let tags = attributes.tags();
+        self.require.iter().all(|tag| tags.contains(tag))
+            && !self.reject.iter().any(|tag| tags.contains(tag))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpisodeKind {
+    Regular,
+    Special,
+    Ova,
+    Recap,
+    CreditlessOp,
+    CreditlessEd,
+}
+
 #[derive(Debug)]
+pub struct BadEpisodeKind;
+impl fmt::Display for BadEpisodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl Error for BadEpisodeKind {}
+
+impl FromStr for EpisodeKind {
+    type Err = BadEpisodeKind;
+
+    fn from_str(s: &str) -> Result<EpisodeKind, BadEpisodeKind> {
+        match s {
+            "regular" => Ok(EpisodeKind::Regular),
+            "special" | "sp" => Ok(EpisodeKind::Special),
+            "ova" | "oad" => Ok(EpisodeKind::Ova),
+            "recap" => Ok(EpisodeKind::Recap),
+            "ncop" => Ok(EpisodeKind::CreditlessOp),
+            "nced" => Ok(EpisodeKind::CreditlessEd),
+            _ => Err(BadEpisodeKind),
+        }
+    }
+}
+
+impl fmt::Display for EpisodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EpisodeKind::Regular => write!(f, "regular"),
+            EpisodeKind::Special => write!(f, "special"),
+            EpisodeKind::Ova => write!(f, "ova"),
+            EpisodeKind::Recap => write!(f, "recap"),
+            EpisodeKind::CreditlessOp => write!(f, "ncop"),
+            EpisodeKind::CreditlessEd => write!(f, "nced"),
+        }
+    }
+}
+
+impl Serialize for EpisodeKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromSql for EpisodeKind {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        value.as_str().and_then(|s| match EpisodeKind::from_str(s) {
+            Ok(kind) => Ok(kind),
+            Err(e) => Err(FromSqlError::Other(Box::new(e))),
+        })
+    }
+}
+
+impl ToSql for EpisodeKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, RusqliteError> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+/// Formats an episode number the way release titles usually do, e.g. "06", "12.5", "SP01".
+pub fn episode_label(kind: EpisodeKind, episode: i32, part: Option<i32>) -> String {
+    match (kind, part) {
+        (_, Some(part)) => {
+            let digits = format!("{:02}", part);
+            match digits.trim_end_matches('0') {
+                "" => format!("{:02}.0", episode),
+                digits => format!("{:02}.{}", episode, digits),
+            }
+        }
+        (EpisodeKind::Regular, None) => format!("{:02}", episode),
+        (EpisodeKind::Special, None) => format!("SP{:02}", episode),
+        (EpisodeKind::Ova, None) => format!("OVA{:02}", episode),
+        (EpisodeKind::Recap, None) => format!("Recap{:02}", episode),
+        (EpisodeKind::CreditlessOp, None) => format!("NCOP{:02}", episode),
+        (EpisodeKind::CreditlessEd, None) => format!("NCED{:02}", episode),
+    }
+}
+
+/// Formats a season and episode like "S01E06", or "S01 SP01" for anything but regular episodes.
+pub fn season_episode_label(season: i32, kind: EpisodeKind, episode: i32, part: Option<i32>) -> String {
+    match (kind, part) {
+        (EpisodeKind::Regular, None) => format!("S{:02}E{:02}", season, episode),
+        _ => format!("S{:02} {}", season, episode_label(kind, episode, part)),
+    }
+}
+
+/// The episodes covered by a batch release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EpisodeRange {
+    pub first: i32,
+    /// None when the batch covers the rest of the season, e.g. "(Season 1) [Batch]"
+    pub last: Option<i32>,
+}
+
+impl EpisodeRange {
+    pub fn contains(&self, episode: i32) -> bool {
+        episode >= self.first && self.last.is_none_or(|last| episode <= last)
+    }
+}
+
+impl fmt::Display for EpisodeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.last {
+            Some(last) => write!(f, "{:02}-{:02}", self.first, last),
+            None => write!(f, "{:02}-", self.first),
+        }
+    }
+}
+
+fn serialize_crc32<S: Serializer>(crc32: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error> {
+    match crc32 {
+        Some(crc32) => serializer.collect_str(&format_args!("{:08X}", crc32)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub struct Episode {
     pub group: String,
     pub name: String,
     pub quality: Option<Quality>,
     pub season: Option<i32>,
     pub episode: i32,
+    /// Every episode in the release, e.g. [1, 2] for "S01E01E02" or "01+02", otherwise
+    /// just `episode`
+    pub episodes: Vec<i32>,
+    pub kind: EpisodeKind,
+    /// The fractional part of episodes like "12.5", in hundredths so "12.5" is 50 and "12.05"
+    /// is 5
+    pub part: Option<i32>,
+    /// Set for batch releases, in which case `episode` is the first episode of the range
+    pub range: Option<EpisodeRange>,
     pub version: i32,
+    /// The checksum tag from the title, e.g. "[E2508E65]"
+    #[serde(serialize_with = "serialize_crc32")]
+    pub crc32: Option<u32>,
+    pub attributes: ReleaseAttributes,
     pub extension: Option<String>,
 }
+
+impl Episode {
+    pub fn label(&self) -> String {
+        match &self.range {
+            Some(range) => format!("Batch {}", range),
+            None if self.is_multi_episode() => self
+                .episodes
+                .iter()
+                .map(|&episode| episode_label(self.kind, episode, self.part))
+                .collect::<Vec<_>>()
+                .join("+"),
+            None => episode_label(self.kind, self.episode, self.part),
+        }
+    }
+
+    pub fn is_batch(&self) -> bool {
+        self.range.is_some()
+    }
+
+    pub fn is_multi_episode(&self) -> bool {
+        self.episodes.len() > 1
+    }
+
+    pub fn entry(&self, link: &str, grabbed: bool) -> EpisodeEntry {
+        self.episode_entry(self.episode, link, grabbed)
+    }
+
+    /// One entry for each episode in the release, all sharing the link.
+    pub fn entries(&self, link: &str, grabbed: bool) -> Vec<EpisodeEntry> {
+        self.episodes
+            .iter()
+            .map(|&episode| self.episode_entry(episode, link, grabbed))
+            .collect()
+    }
+
+    fn episode_entry(&self, episode: i32, link: &str, grabbed: bool) -> EpisodeEntry {
+        EpisodeEntry {
+            season: self.season.unwrap_or(1),
+            episode,
+            kind: self.kind,
+            part: self.part,
+            version: self.version,
+            crc32: self.crc32,
+            attributes: self.attributes.clone(),
+            link: link.to_string(),
+            grabbed,
+        }
+    }
+}
+
+/// An episode row to be written to the database.
+#[derive(Debug, Clone)]
+pub struct EpisodeEntry {
+    pub season: i32,
+    pub episode: i32,
+    pub kind: EpisodeKind,
+    pub part: Option<i32>,
+    pub version: i32,
+    pub crc32: Option<u32>,
+    pub attributes: ReleaseAttributes,
+    pub link: String,
+    pub grabbed: bool,
+}
+
+impl EpisodeEntry {
+    pub fn season_label(&self) -> String {
+        season_episode_label(self.season, self.kind, self.episode, self.part)
+    }
+}
+
+#[derive(Debug)]
+pub struct ShowSummary {
+    pub show_id: i64,
+    pub group: String,
+    pub name: String,
+    pub quality: Option<Quality>,
+    pub latest_season: Option<i32>,
+    pub latest_episode: Option<i32>,
+    pub latest_version: Option<i32>,
+    pub grabbed: i64,
+    pub ungrabbed: i64,
+    pub last_grabbed_on: Option<NaiveDateTime>,
+    pub status: ShowStatus,
+}
+
+#[derive(Debug)]
+pub struct EpisodeRecord {
+    pub show_id: i64,
+    pub group: String,
+    pub name: String,
+    pub quality: Option<Quality>,
+    pub season: i32,
+    pub episode: i32,
+    pub kind: EpisodeKind,
+    pub part: Option<i32>,
+    pub version: i32,
+    pub link: String,
+    pub grabbed: bool,
+    pub grabbed_on: Option<NaiveDateTime>,
+}
+
+impl EpisodeRecord {
+    pub fn label(&self) -> String {
+        episode_label(self.kind, self.episode, self.part)
+    }
+
+    pub fn season_label(&self) -> String {
+        season_episode_label(self.season, self.kind, self.episode, self.part)
+    }
+}
@@ -0,0 +1,354 @@
+use crate::curl;
+use crate::rss;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Error as XmlError;
+use quick_xml::Reader;
+use std::cell::OnceCell;
+use std::io::BufRead;
+use url::Url;
+
+const PAGE_SIZE: u32 = 100;
+
+#[derive(Debug)]
+pub enum Error {
+    UrlParseError(url::ParseError),
+    CurlError(curl::Error),
+    RssError(rss::Error),
+    /// An `<error code="100" description="Incorrect user credentials"/>` response
+    ApiError(String, String),
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Error {
+        Error::UrlParseError(err)
+    }
+}
+
+impl From<curl::Error> for Error {
+    fn from(err: curl::Error) -> Error {
+        Error::CurlError(err)
+    }
+}
+
+impl From<rss::Error> for Error {
+    fn from(err: rss::Error) -> Error {
+        Error::RssError(err)
+    }
+}
+
+impl From<XmlError> for Error {
+    fn from(err: XmlError) -> Error {
+        Error::RssError(rss::Error::Xml(err))
+    }
+}
+
+/// What an indexer supports, from `t=caps`.
+#[derive(Debug, Default)]
+pub struct Caps {
+    pub max_limit: Option<u32>,
+    pub search: bool,
+    pub tv_search: bool,
+    /// e.g. ["q", "season", "ep"]
+    pub tv_search_params: Vec<String>,
+    pub categories: Vec<Category>,
+}
+
+#[derive(Debug, Default)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub subcats: Vec<Category>,
+}
+
+impl Caps {
+    pub fn supports_tv_search(&self) -> bool {
+        self.tv_search
+            && ["season", "ep"]
+                .iter()
+                .all(|param| self.tv_search_params.iter().any(|p| p == param))
+    }
+}
+
+fn attribute<R: BufRead>(
+    reader: &Reader<R>,
+    element: &BytesStart,
+    key: &str,
+) -> Result<Option<String>, Error> {
+    match element.try_get_attribute(key)? {
+        Some(attr) => Ok(Some(attr.decode_and_unescape_value(reader)?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+/// Reads a caps response. Torznab indexers answer `t=caps` in the same format.
+pub fn read_caps<R: BufRead>(reader: R) -> Result<Caps, Error> {
+    let mut reader = Reader::from_reader(reader);
+    reader.trim_text(true).expand_empty_elements(true);
+
+    let mut buf = Vec::new();
+    let mut caps = Caps::default();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) => match e.name().as_ref() {
+                b"error" => return Err(api_error(&reader, e)?),
+                b"limits" => {
+                    caps.max_limit = attribute(&reader, e, "max")?.and_then(|max| max.parse().ok())
+                }
+                b"search" => {
+                    caps.search = attribute(&reader, e, "available")?.as_deref() == Some("yes")
+                }
+                b"tv-search" => {
+                    caps.tv_search = attribute(&reader, e, "available")?.as_deref() == Some("yes");
+                    caps.tv_search_params = attribute(&reader, e, "supportedParams")?
+                        .map(|params| params.split(',').map(|p| p.trim().to_string()).collect())
+                        .unwrap_or_default();
+                }
+                b"category" => caps.categories.push(Category {
+                    id: attribute(&reader, e, "id")?.unwrap_or_default(),
+                    name: attribute(&reader, e, "name")?.unwrap_or_default(),
+                    subcats: Vec::new(),
+                }),
+                b"subcat" => {
+                    if let Some(category) = caps.categories.last_mut() {
+                        category.subcats.push(Category {
+                            id: attribute(&reader, e, "id")?.unwrap_or_default(),
+                            name: attribute(&reader, e, "name")?.unwrap_or_default(),
+                            subcats: Vec::new(),
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(caps)
+}
+
+fn api_error<R: BufRead>(reader: &Reader<R>, element: &BytesStart) -> Result<Error, Error> {
+    Ok(Error::ApiError(
+        attribute(reader, element, "code")?.unwrap_or_default(),
+        attribute(reader, element, "description")?.unwrap_or_default(),
+    ))
+}
+
+/// Reads a search response, turning an api error into an `Err` rather than no results.
+pub fn read_results(body: &[u8]) -> Result<Vec<rss::Item>, Error> {
+    let mut reader = Reader::from_reader(body);
+    reader.trim_text(true).expand_empty_elements(true);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.name().as_ref() == b"error" => {
+                return Err(api_error(&reader, e)?)
+            }
+            Event::Start(_) | Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(rss::read_from(body)?)
+}
+
+/// A client for the Newznab api that NZBGeek, NZBPlanet, nZEDb and friends expose.
+pub struct NewznabClient {
+    url: String,
+    apikey: String,
+    /// e.g. ["5070"] for TV > Anime
+    categories: Vec<String>,
+    caps: OnceCell<Caps>,
+}
+
+impl NewznabClient {
+    pub fn new(url: &str, apikey: &str, categories: &[String]) -> NewznabClient {
+        NewznabClient {
+            url: url.to_string(),
+            apikey: apikey.to_string(),
+            categories: categories.to_vec(),
+            caps: OnceCell::new(),
+        }
+    }
+
+    fn api_url(&self, t: &str) -> Result<Url, Error> {
+        let url = Url::parse(&self.url)?;
+        let mut url = url.join("api")?;
+        url.query_pairs_mut()
+            .append_pair("t", t)
+            .append_pair("apikey", &self.apikey);
+        Ok(url)
+    }
+
+    fn search_url(&self, t: &str, page: u8) -> Result<Url, Error> {
+        let mut url = self.api_url(t)?;
+        {
+            let mut query = url.query_pairs_mut();
+            if !self.categories.is_empty() {
+                query.append_pair("cat", &self.categories.join(","));
+            }
+            query
+                .append_pair("limit", &PAGE_SIZE.to_string())
+                .append_pair(
+                    "offset",
+                    &(u32::from(page.max(1) - 1) * PAGE_SIZE).to_string(),
+                );
+        }
+        Ok(url)
+    }
+
+    // https://newznab.readthedocs.io/en/latest/misc/api/#caps
+    pub fn caps(&self) -> Result<&Caps, Error> {
+        if let Some(caps) = self.caps.get() {
+            return Ok(caps);
+        }
+        let response = curl::get(self.api_url("caps")?)?;
+        let caps = read_caps(&response.body[..])?;
+        Ok(self.caps.get_or_init(|| caps))
+    }
+
+    // https://newznab.readthedocs.io/en/latest/misc/api/#search
+    pub fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        let mut url = self.search_url("search", page.unwrap_or(1))?;
+        url.query_pairs_mut().append_pair("q", terms);
+        results_from_url(url)
+    }
+
+    /// The latest releases in the configured categories.
+    pub fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        results_from_url(self.search_url("search", page)?)
+    }
+
+    // https://newznab.readthedocs.io/en/latest/misc/api/#tv-search
+    pub fn tvsearch(
+        &self,
+        name: &str,
+        season: i32,
+        episode: i32,
+        page: Option<u8>,
+    ) -> Result<Vec<rss::Item>, Error> {
+        let mut url = self.search_url("tvsearch", page.unwrap_or(1))?;
+        url.query_pairs_mut()
+            .append_pair("q", name)
+            .append_pair("season", &season.to_string())
+            .append_pair("ep", &episode.to_string());
+        results_from_url(url)
+    }
+}
+
+fn results_from_url(url: Url) -> Result<Vec<rss::Item>, Error> {
+    let response = curl::get(url)?;
+    read_results(&response.body[..])
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_read_caps() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<caps>
+  <server version="1.0" title="NZBGeek" strapline="NZBGeek API" url="https://nzbgeek.info/"/>
+  <limits max="100" default="100"/>
+  <registration available="no" open="no"/>
+  <searching>
+    <search available="yes" supportedParams="q"/>
+    <tv-search available="yes" supportedParams="q,rid,tvdbid,season,ep"/>
+    <movie-search available="yes" supportedParams="q,imdbid"/>
+  </searching>
+  <categories>
+    <category id="2000" name="Movies">
+      <subcat id="2040" name="HD"/>
+    </category>
+    <category id="5000" name="TV">
+      <subcat id="5040" name="HD"/>
+      <subcat id="5070" name="Anime"/>
+    </category>
+  </categories>
+</caps>
+"#;
+        let caps = read_caps(data.as_bytes()).unwrap();
+        assert_eq!(caps.max_limit, Some(100));
+        assert!(caps.search);
+        assert!(caps.supports_tv_search());
+        assert_eq!(caps.categories.len(), 2);
+        assert_eq!(caps.categories[1].name, "TV");
+        assert_eq!(caps.categories[1].subcats[1].id, "5070");
+        assert_eq!(caps.categories[1].subcats[1].name, "Anime");
+
+        let error = r#"<?xml version="1.0" encoding="UTF-8"?>
+<error code="100" description="Incorrect user credentials"/>"#;
+        match read_caps(error.as_bytes()) {
+            Err(Error::ApiError(code, description)) => {
+                assert_eq!(code, "100");
+                assert_eq!(description, "Incorrect user credentials");
+            }
+            other => panic!("expected an api error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_results() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:newznab="http://www.newznab.com/DTD/2010/feeds/attributes/">
+<channel>
+  <atom:link href="https://api.nzbgeek.info/api" rel="self" type="application/rss+xml"/>
+  <title>NZBGeek</title>
+  <description>NZBGeek API Feed</description>
+  <newznab:response offset="0" total="1"/>
+  <item>
+    <title>[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv</title>
+    <guid isPermaLink="true">https://nzbgeek.info/geekseek.php?guid=0a1b2c3d</guid>
+    <link>https://api.nzbgeek.info/api?t=get&amp;id=0a1b2c3d&amp;apikey=secret</link>
+    <comments>https://nzbgeek.info/geekseek.php?guid=0a1b2c3d</comments>
+    <pubDate>Sat, 14 Nov 2020 16:02:44 +0000</pubDate>
+    <category>TV &gt; Anime</category>
+    <description>[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv</description>
+    <enclosure url="https://api.nzbgeek.info/api?t=get&amp;id=0a1b2c3d&amp;apikey=secret" length="1503238554" type="application/x-nzb"/>
+    <newznab:attr name="category" value="5000"/>
+    <newznab:attr name="category" value="5070"/>
+    <newznab:attr name="size" value="1503238554"/>
+    <newznab:attr name="grabs" value="213"/>
+    <newznab:attr name="usenetdate" value="Sat, 14 Nov 2020 15:40:12 +0000"/>
+  </item>
+</channel>
+</rss>
+"#;
+        let items = read_results(data.as_bytes()).unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(
+            item.title,
+            "[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv"
+        );
+        assert_eq!(
+            item.nzb_link,
+            "https://api.nzbgeek.info/api?t=get&id=0a1b2c3d&apikey=secret"
+        );
+        assert_eq!(item.category.as_deref(), Some("TV > Anime"));
+        assert_eq!(item.size, Some(1503238554));
+        assert_eq!(item.grabs, Some(213));
+        assert_eq!(
+            item.usenet_date.map(|date| date.to_string()).as_deref(),
+            Some("2020-11-14 15:40:12")
+        );
+
+        let error = r#"<error code="101" description="Account suspended"/>"#;
+        assert!(matches!(
+            read_results(error.as_bytes()),
+            Err(Error::ApiError(code, _)) if code == "101"
+        ));
+    }
+
+    #[test]
+    fn test_search_url() {
+        let client =
+            NewznabClient::new("https://api.nzbgeek.info/", "secret", &["5070".to_string()]);
+        assert_eq!(
+            client.search_url("tvsearch", 3).unwrap().as_str(),
+            "https://api.nzbgeek.info/api?t=tvsearch&apikey=secret&cat=5070&limit=100&offset=200"
+        );
+    }
+}
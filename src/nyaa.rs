@@ -0,0 +1,186 @@
+use crate::curl;
+use crate::rss;
+use url::Url;
+
+const NYAA_URL: &str = "https://nyaa.si/";
+// Anime - English-translated
+const DEFAULT_CATEGORY: &str = "1_2";
+
+#[derive(Debug)]
+pub enum Error {
+    UrlParseError(url::ParseError),
+    CurlError(curl::Error),
+    RssError(rss::Error),
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Error {
+        Error::UrlParseError(err)
+    }
+}
+
+impl From<curl::Error> for Error {
+    fn from(err: curl::Error) -> Error {
+        Error::CurlError(err)
+    }
+}
+
+impl From<rss::Error> for Error {
+    fn from(err: rss::Error) -> Error {
+        Error::RssError(err)
+    }
+}
+
+pub struct NyaaClient {
+    url: String,
+    category: String,
+    trusted_only: bool,
+}
+
+impl NyaaClient {
+    pub fn new(url: Option<&str>, category: Option<&str>, trusted_only: bool) -> NyaaClient {
+        NyaaClient {
+            url: url.unwrap_or(NYAA_URL).to_string(),
+            category: category.unwrap_or(DEFAULT_CATEGORY).to_string(),
+            trusted_only,
+        }
+    }
+
+    fn rss_url(&self, terms: Option<&str>, page: u8) -> Result<Url, Error> {
+        let mut url = Url::parse(&self.url)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("page", "rss")
+                .append_pair("c", &self.category)
+                // 0 is no filter, 2 is trusted uploaders only
+                .append_pair("f", if self.trusted_only { "2" } else { "0" });
+            if let Some(terms) = terms {
+                query.append_pair("q", terms);
+            }
+            if page > 1 {
+                query.append_pair("p", &page.to_string());
+            }
+        }
+        Ok(url)
+    }
+
+    pub fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        results_from_url(self.rss_url(Some(terms), page.unwrap_or(1))?)
+    }
+
+    pub fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        results_from_url(self.rss_url(None, page)?)
+    }
+}
+
+fn results_from_url(url: Url) -> Result<Vec<rss::Item>, Error> {
+    let response = curl::get(url)?;
+    read_from(&response.body[..])
+}
+
+// nyaa links straight to the torrent rather than using an enclosure
+fn read_from(body: &[u8]) -> Result<Vec<rss::Item>, Error> {
+    let mut items = rss::read_from(body)?;
+    for item in &mut items {
+        if item.torrent_link.is_empty() {
+            item.torrent_link = item.link.clone();
+        }
+        if let Some(id) = item.guid.strip_prefix("https://nyaa.si/view/") {
+            item.guid = id.to_string();
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_read_from_xml() {
+        let data = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss xmlns:atom="http://www.w3.org/2005/Atom" xmlns:nyaa="https://nyaa.si/xmlns/nyaa" version="2.0">
+	<channel>
+		<title>Nyaa - Home - Torrent File RSS</title>
+		<description>RSS Feed for Home</description>
+		<link>https://nyaa.si/</link>
+		<atom:link href="https://nyaa.si/?page=rss" rel="self" type="application/rss+xml" />
+		<item>
+			<title>[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv</title>
+				<link>https://nyaa.si/download/1300519.torrent</link>
+				<guid isPermaLink="true">https://nyaa.si/view/1300519</guid>
+				<pubDate>Sat, 14 Nov 2020 15:32:03 -0000</pubDate>
+				<nyaa:seeders>2311</nyaa:seeders>
+				<nyaa:leechers>38</nyaa:leechers>
+				<nyaa:downloads>25361</nyaa:downloads>
+				<nyaa:infoHash>a0bbdb2bd3e1bed4e2bfc20bd0a5ff0a6d8f4d43</nyaa:infoHash>
+				<nyaa:categoryId>1_2</nyaa:categoryId>
+				<nyaa:category>Anime - English-translated</nyaa:category>
+				<nyaa:size>1.4 GiB</nyaa:size>
+				<nyaa:comments>3</nyaa:comments>
+				<nyaa:trusted>Yes</nyaa:trusted>
+				<nyaa:remake>No</nyaa:remake>
+				<description><![CDATA[<a href="https://nyaa.si/view/1300519">#1300519 | [SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv</a> | 1.4 GiB | Anime - English-translated | A0BBDB2BD3E1BED4E2BFC20BD0A5FF0A6D8F4D43]]></description>
+		</item>
+		<item>
+			<title>[Judas] Dorohedoro - 09 [1080p][HEVC x265 10bit][Eng-Subs]</title>
+				<link>https://nyaa.si/download/1227240.torrent</link>
+				<guid isPermaLink="true">https://nyaa.si/view/1227240</guid>
+				<pubDate>Sun, 08 Mar 2020 08:56:16 -0000</pubDate>
+				<nyaa:seeders>12</nyaa:seeders>
+				<nyaa:leechers>0</nyaa:leechers>
+				<nyaa:downloads>4021</nyaa:downloads>
+				<nyaa:infoHash>c9132ccb1d4d0bc5f29e1064143a96e468cf2870</nyaa:infoHash>
+				<nyaa:categoryId>1_2</nyaa:categoryId>
+				<nyaa:category>Anime - English-translated</nyaa:category>
+				<nyaa:size>283.5 MiB</nyaa:size>
+				<nyaa:comments>0</nyaa:comments>
+				<nyaa:trusted>No</nyaa:trusted>
+				<nyaa:remake>Yes</nyaa:remake>
+				<description><![CDATA[<a href="https://nyaa.si/view/1227240">#1227240 | [Judas] Dorohedoro - 09 [1080p][HEVC x265 10bit][Eng-Subs]</a> | 283.5 MiB | Anime - English-translated | C9132CCB1D4D0BC5F29E1064143A96E468CF2870]]></description>
+		</item>
+	</channel>
+</rss>
+"#;
+
+        let items = read_from(data.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+
+        let item = &items[0];
+        assert_eq!(item.title, "[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv");
+        assert_eq!(item.torrent_link, "https://nyaa.si/download/1300519.torrent");
+        assert_eq!(item.nzb_link, "");
+        assert_eq!(item.guid, "1300519");
+        assert_eq!(item.pub_date.to_string(), "2020-11-14 15:32:03");
+        assert_eq!(item.seeders, Some(2311));
+        assert_eq!(item.leechers, Some(38));
+        assert_eq!(
+            item.info_hash.as_deref(),
+            Some("a0bbdb2bd3e1bed4e2bfc20bd0a5ff0a6d8f4d43")
+        );
+        assert_eq!(item.category.as_deref(), Some("Anime - English-translated"));
+        assert_eq!(item.size, Some(1503238554));
+        assert!(item.trusted);
+        assert!(!item.remake);
+
+        let item = &items[1];
+        assert_eq!(item.size, Some(297271296));
+        assert!(!item.trusted);
+        assert!(item.remake);
+    }
+
+    #[test]
+    fn test_rss_url() {
+        let nyaa = NyaaClient::new(None, None, true);
+        assert_eq!(
+            nyaa.rss_url(Some("SubsPlease Jujutsu Kaisen"), 2).unwrap().as_str(),
+            "https://nyaa.si/?page=rss&c=1_2&f=2&q=SubsPlease+Jujutsu+Kaisen&p=2"
+        );
+        assert_eq!(
+            nyaa.rss_url(None, 1).unwrap().as_str(),
+            "https://nyaa.si/?page=rss&c=1_2&f=2"
+        );
+    }
+}
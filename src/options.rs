@@ -1,10 +1,12 @@
-use crate::models::Quality;
+use crate::models::{EpisodeKind, Quality};
 use gumdrop::Options;
 
 #[derive(Debug, Options)]
 pub struct ToshoOptions {
     #[options(help = "print help message")]
     help: bool,
+    #[options(no_short, help = "show what would change without writing anything")]
+    pub dry_run: bool,
     #[options(command, required)]
     pub command: Option<Command>,
 }
@@ -21,6 +23,30 @@ pub enum Command {
     Recheck(RecheckOpts),
     #[options(help = "check for new bookmarks on dognzb")]
     Dog(CheckOpts),
+    #[options(help = "list tracked shows and their progress")]
+    List(ListOpts),
+    #[options(help = "stop tracking a show and delete its episodes")]
+    Remove(RemoveOpts),
+    #[options(help = "stop checking for a show but keep its history")]
+    Archive(ArchiveOpts),
+    #[options(help = "change the group, name or quality of a show")]
+    Edit(EditOpts),
+    #[options(help = "stop queueing episodes of a show until resumed")]
+    Pause(ShowOpts),
+    #[options(help = "resume queueing episodes of a paused show")]
+    Resume(ShowOpts),
+    #[options(help = "manage alternate titles for a show")]
+    Alias(AliasOpts),
+    #[options(help = "search all sources and show how titles are parsed")]
+    Search(SearchOpts),
+    #[options(help = "list episodes and when they were grabbed")]
+    History(HistoryOpts),
+    #[options(help = "manually mark episodes as grabbed or not grabbed")]
+    Mark(MarkOpts),
+    #[options(help = "check downloaded files against their CRC32 checksums")]
+    Verify(VerifyOpts),
+    #[options(help = "show how titles are parsed, as JSON")]
+    Parse(ParseOpts),
 }
 
 #[derive(Debug, Options)]
@@ -37,6 +63,31 @@ pub struct AddOpts {
     pub start: Option<i32>,
     #[options(help = "The season to start from")]
     pub season: Option<i32>,
+    #[options(no_short, help = "The SABnzbd category to queue episodes in")]
+    pub category: Option<String>,
+    #[options(
+        no_short,
+        help = "The SABnzbd priority (-2 paused, -1 low, 0 normal, 1 high, 2 force)"
+    )]
+    pub priority: Option<i32>,
+    #[options(
+        no_short,
+        help = "The SABnzbd post-processing (0 none, 1 repair, 2 unpack, 3 delete)"
+    )]
+    pub pp: Option<i32>,
+    #[options(no_short, help = "The SABnzbd post-processing script")]
+    pub script: Option<String>,
+    #[options(no_short, help = "Also track specials, OVAs, recaps and creditless OP/EDs")]
+    pub specials: bool,
+    #[options(
+        no_short,
+        help = "Also match names with a season marker, e.g. \"Show S2\" or \"Show II\""
+    )]
+    pub merge_seasons: bool,
+    #[options(no_short, help = "Only accept releases with these attributes, e.g. hevc,10bit")]
+    pub require: Option<String>,
+    #[options(no_short, help = "Skip releases with any of these attributes, e.g. raw,bd")]
+    pub reject: Option<String>,
 }
 
 #[derive(Debug, Options)]
@@ -56,3 +107,261 @@ pub struct CheckOpts {
     #[options(help = "print help message")]
     help: bool,
 }
+
+#[derive(Debug, Options)]
+pub struct ListOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "Only list shows from this group (wildcards allowed)")]
+    pub group: Option<String>,
+    #[options(help = "Only list shows matching this name (wildcards allowed)")]
+    pub name: Option<String>,
+    #[options(help = "Only list shows with ungrabbed episodes")]
+    pub ungrabbed_only: bool,
+}
+
+#[derive(Debug, Options)]
+pub struct RemoveOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The show name", required)]
+    pub show: String,
+    #[options(help = "The group name, if several shows share the name")]
+    pub group: Option<String>,
+    #[options(help = "The show quality, if several shows share the name")]
+    pub quality: Option<Quality>,
+}
+
+#[derive(Debug, Options)]
+pub struct ArchiveOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The show name", required)]
+    pub show: String,
+    #[options(help = "The group name, if several shows share the name")]
+    pub group: Option<String>,
+    #[options(help = "The show quality, if several shows share the name")]
+    pub quality: Option<Quality>,
+    #[options(help = "Restore an archived show")]
+    pub undo: bool,
+}
+
+#[derive(Debug, Options)]
+pub struct EditOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The show name", required)]
+    pub show: String,
+    #[options(help = "The group name, if several shows share the name")]
+    pub group: Option<String>,
+    #[options(help = "The show quality, if several shows share the name")]
+    pub quality: Option<Quality>,
+    #[options(no_short, help = "The new group name (wildcards allowed)")]
+    pub new_group: Option<String>,
+    #[options(no_short, help = "The new show name")]
+    pub new_name: Option<String>,
+    #[options(no_short, help = "The new show quality")]
+    pub new_quality: Option<Quality>,
+    #[options(no_short, help = "Clear the show quality")]
+    pub no_quality: bool,
+    #[options(help = "Search for episodes released under the new details")]
+    pub backfill: bool,
+    #[options(help = "The episode number to backfill from")]
+    pub start: Option<i32>,
+    #[options(help = "The season to backfill from")]
+    pub season: Option<i32>,
+    #[options(no_short, help = "The SABnzbd category to queue episodes in")]
+    pub category: Option<String>,
+    #[options(
+        no_short,
+        help = "The SABnzbd priority (-2 paused, -1 low, 0 normal, 1 high, 2 force)"
+    )]
+    pub priority: Option<i32>,
+    #[options(
+        no_short,
+        help = "The SABnzbd post-processing (0 none, 1 repair, 2 unpack, 3 delete)"
+    )]
+    pub pp: Option<i32>,
+    #[options(no_short, help = "The SABnzbd post-processing script")]
+    pub script: Option<String>,
+    #[options(no_short, help = "Reset the SABnzbd options to the configured defaults")]
+    pub reset_download_options: bool,
+    #[options(no_short, help = "Also track specials, OVAs, recaps and creditless OP/EDs")]
+    pub specials: bool,
+    #[options(no_short, help = "Only track regular episodes")]
+    pub no_specials: bool,
+    #[options(
+        no_short,
+        help = "Also match names with a season marker, e.g. \"Show S2\" or \"Show II\""
+    )]
+    pub merge_seasons: bool,
+    #[options(no_short, help = "Only match the exact name")]
+    pub no_merge_seasons: bool,
+    #[options(
+        no_short,
+        help = "Only accept releases with these attributes, e.g. hevc,10bit (\"\" to clear)"
+    )]
+    pub require: Option<String>,
+    #[options(
+        no_short,
+        help = "Skip releases with any of these attributes, e.g. raw,bd (\"\" to clear)"
+    )]
+    pub reject: Option<String>,
+}
+
+#[derive(Debug, Options)]
+pub struct ShowOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The show name", required)]
+    pub show: String,
+    #[options(help = "The group name, if several shows share the name")]
+    pub group: Option<String>,
+    #[options(help = "The show quality, if several shows share the name")]
+    pub quality: Option<Quality>,
+}
+
+#[derive(Debug, Options)]
+pub struct AliasOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(command, required)]
+    pub command: Option<AliasCommand>,
+}
+
+#[derive(Debug, Options)]
+pub enum AliasCommand {
+    #[options(help = "add an alternate title for a show")]
+    Add(AliasEditOpts),
+    #[options(help = "remove an alternate title from a show")]
+    Remove(AliasEditOpts),
+    #[options(help = "list alternate titles")]
+    List(AliasListOpts),
+}
+
+#[derive(Debug, Options)]
+pub struct AliasEditOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The show name", required)]
+    pub show: String,
+    #[options(free, help = "The alternate title", required)]
+    pub alias: String,
+    #[options(help = "The group name, if several shows share the name")]
+    pub group: Option<String>,
+    #[options(help = "The show quality, if several shows share the name")]
+    pub quality: Option<Quality>,
+}
+
+#[derive(Debug, Options)]
+pub struct AliasListOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "Only list aliases of shows matching this name (wildcards allowed)")]
+    pub show: Option<String>,
+}
+
+#[derive(Debug, Options)]
+pub struct SearchOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The search terms", required)]
+    pub terms: Vec<String>,
+    #[options(help = "How many result pages to fetch (default 1)")]
+    pub pages: Option<u8>,
+    #[options(help = "Add the show parsed from this result number")]
+    pub add: Option<usize>,
+    #[options(help = "The episode number to start from when adding")]
+    pub start: Option<i32>,
+    #[options(help = "The season to start from when adding")]
+    pub season: Option<i32>,
+    #[options(no_short, help = "The SABnzbd category to queue episodes in")]
+    pub category: Option<String>,
+    #[options(
+        no_short,
+        help = "The SABnzbd priority (-2 paused, -1 low, 0 normal, 1 high, 2 force)"
+    )]
+    pub priority: Option<i32>,
+    #[options(
+        no_short,
+        help = "The SABnzbd post-processing (0 none, 1 repair, 2 unpack, 3 delete)"
+    )]
+    pub pp: Option<i32>,
+    #[options(no_short, help = "The SABnzbd post-processing script")]
+    pub script: Option<String>,
+    #[options(no_short, help = "Also track specials, OVAs, recaps and creditless OP/EDs")]
+    pub specials: bool,
+    #[options(
+        no_short,
+        help = "Also match names with a season marker, e.g. \"Show S2\" or \"Show II\""
+    )]
+    pub merge_seasons: bool,
+    #[options(no_short, help = "Only accept releases with these attributes, e.g. hevc,10bit")]
+    pub require: Option<String>,
+    #[options(no_short, help = "Skip releases with any of these attributes, e.g. raw,bd")]
+    pub reject: Option<String>,
+}
+
+#[derive(Debug, Options)]
+pub struct HistoryOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "Only list episodes of shows matching this name (wildcards allowed)")]
+    pub show: Option<String>,
+    #[options(help = "Only list episodes grabbed on or after this date (YYYY-MM-DD)")]
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Options)]
+pub struct MarkOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The show name", required)]
+    pub show: String,
+    #[options(free, help = "The episodes to mark, e.g. 5, 1-12 or 1,3,5-7", required)]
+    pub episodes: String,
+    #[options(help = "The group name, if several shows share the name")]
+    pub group: Option<String>,
+    #[options(help = "The show quality, if several shows share the name")]
+    pub quality: Option<Quality>,
+    #[options(help = "The season of the episodes, the show's latest season by default")]
+    pub season: Option<i32>,
+    #[options(help = "Only mark this version of the episodes")]
+    pub version: Option<i32>,
+    #[options(help = "The kind of episode (regular, special, ova, recap, ncop, nced)")]
+    pub kind: Option<EpisodeKind>,
+    #[options(
+        no_short,
+        help = "The digits after the point of fractional episodes, e.g. 5 for 12.5 or 05 for 12.05"
+    )]
+    pub part: Option<String>,
+    #[options(no_short, help = "Mark the episodes as grabbed")]
+    pub grabbed: bool,
+    #[options(no_short, help = "Mark the episodes as not grabbed so queue sends them again")]
+    pub ungrabbed: bool,
+    #[options(
+        no_short,
+        help = "With --grabbed, record episodes tosho hasn't seen, e.g. ones fetched elsewhere"
+    )]
+    pub record: bool,
+}
+
+#[derive(Debug, Options)]
+pub struct VerifyOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The downloaded files to check", required)]
+    pub files: Vec<String>,
+    #[options(no_short, help = "Mark episodes that fail as not grabbed so queue sends them again")]
+    pub requeue: bool,
+}
+
+#[derive(Debug, Options)]
+pub struct ParseOpts {
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(free, help = "The titles to parse, read from stdin when none are given")]
+    pub titles: Vec<String>,
+    #[options(no_short, help = "Replay a corpus file of titles and their expected fields")]
+    pub corpus: Option<String>,
+}
@@ -34,6 +34,29 @@ pub struct Item {
     pub torrent_link: String,
     pub pub_date: NaiveDateTime,
     pub guid: String,
+    /// In bytes
+    pub size: Option<u64>,
+    pub info_hash: Option<String>,
+    pub magnet_link: Option<String>,
+    pub seeders: Option<u32>,
+    pub leechers: Option<u32>,
+    /// e.g. "Anime - English-translated"
+    pub category: Option<String>,
+    /// Uploaded by someone the indexer trusts
+    pub trusted: bool,
+    /// A reupload of someone else's release
+    pub remake: bool,
+    /// How many times an indexer has handed out the nzb
+    pub grabs: Option<u32>,
+    /// When the release was posted to usenet
+    pub usenet_date: Option<NaiveDateTime>,
+    pub file_count: Option<u32>,
+    /// AniDB anime, episode and file ids
+    pub anidb_aid: Option<u32>,
+    pub anidb_eid: Option<u32>,
+    pub anidb_fid: Option<u32>,
+    /// Animetosho's processing status, e.g. "complete" or "skipped"
+    pub status: Option<String>,
 }
 
 impl Default for Item {
@@ -45,6 +68,21 @@ impl Default for Item {
             torrent_link: String::default(),
             pub_date: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
             guid: String::default(),
+            size: None,
+            info_hash: None,
+            magnet_link: None,
+            seeders: None,
+            leechers: None,
+            category: None,
+            trusted: false,
+            remake: false,
+            grabs: None,
+            usenet_date: None,
+            file_count: None,
+            anidb_aid: None,
+            anidb_eid: None,
+            anidb_fid: None,
+            status: None,
         }
     }
 }
@@ -56,6 +94,13 @@ struct Enclosure {
     mime_type: String,
 }
 
+// an extra attribute from an indexer api, e.g. <newznab:attr name="grabs" value="123"/>
+#[derive(Default)]
+struct IndexerAttr {
+    name: String,
+    value: String,
+}
+
 pub fn read_from<R: BufRead>(reader: R) -> Result<Vec<Item>, Error> {
     let mut reader = Reader::from_reader(reader);
     reader.trim_text(true).expand_empty_elements(true);
@@ -87,12 +132,17 @@ impl Item {
     fn from_xml<R: BufRead>(reader: &mut Reader<R>, _: Attributes) -> Result<Self, Error> {
         let mut item = Item::default();
         let mut buf = Vec::new();
+        // torznab peers include the seeders, which may come after them
+        let mut peers: Option<u32> = None;
 
         loop {
             match reader.read_event_into(&mut buf)? {
                 Event::Start(e) => match e.name().as_ref() {
                     b"enclosure" => {
                         let enclosure = Enclosure::from_xml(reader, e.attributes())?;
+                        if item.size.is_none() {
+                            item.size = enclosure.length.parse().ok().filter(|&size| size > 0);
+                        }
                         match enclosure.mime_type.as_ref() {
                             "application/x-nzb" => {
                                 item.nzb_link = enclosure.url;
@@ -105,6 +155,7 @@ impl Item {
                     }
                     b"title" => item.title = element_text(reader)?,
                     b"link" => item.link = element_text(reader)?,
+                    b"category" => item.category = Some(element_text(reader)?),
                     b"pubDate" => {
                         item.pub_date =
                             DateTime::parse_from_rfc2822(&element_text(reader)?)?.naive_utc()
@@ -119,6 +170,26 @@ impl Item {
                             guid.strip_prefix("https://animetosho.org/view/")
                         {
                             item.guid = suffix.to_string();
+                        } else {
+                            item.guid = guid;
+                        }
+                    }
+                    // https://nyaa.si/xmlns/nyaa
+                    b"nyaa:infoHash" => item.info_hash = Some(element_text(reader)?),
+                    b"nyaa:seeders" => item.seeders = element_text(reader)?.parse().ok(),
+                    b"nyaa:leechers" => item.leechers = element_text(reader)?.parse().ok(),
+                    b"nyaa:size" => item.size = parse_size(&element_text(reader)?),
+                    b"nyaa:category" => item.category = Some(element_text(reader)?),
+                    b"nyaa:trusted" => item.trusted = element_text(reader)? == "Yes",
+                    b"nyaa:remake" => item.remake = element_text(reader)? == "Yes",
+                    // https://newznab.readthedocs.io/en/latest/misc/api/#newznab-attributes
+                    // https://torznab.github.io/spec-1.3-draft/torznab/Specification-v1.3.html
+                    b"newznab:attr" | b"torznab:attr" => {
+                        let attr = IndexerAttr::from_xml(reader, e.attributes(), e.name())?;
+                        if attr.name == "peers" {
+                            peers = attr.value.parse().ok();
+                        } else {
+                            item.set_indexer_attr(&attr.name, &attr.value);
                         }
                     }
                     _ => {
@@ -131,10 +202,53 @@ impl Item {
             }
             buf.clear();
         }
+        if item.leechers.is_none() {
+            item.leechers = peers
+                .zip(item.seeders)
+                .map(|(peers, seeders)| peers.saturating_sub(seeders));
+        }
         Ok(item)
     }
 }
 
+impl Item {
+    fn set_indexer_attr(&mut self, name: &str, value: &str) {
+        match name {
+            "size" => self.size = value.parse().ok(),
+            "grabs" => self.grabs = value.parse().ok(),
+            "seeders" => self.seeders = value.parse().ok(),
+            "leechers" => self.leechers = value.parse().ok(),
+            "infohash" => self.info_hash = Some(value.to_lowercase()),
+            "magneturl" => self.magnet_link = Some(value.to_string()),
+            "usenetdate" => {
+                self.usenet_date = DateTime::parse_from_rfc2822(value)
+                    .ok()
+                    .map(|date| date.naive_utc())
+            }
+            _ => {}
+        }
+    }
+}
+
+impl IndexerAttr {
+    fn from_xml<R: BufRead>(
+        reader: &mut Reader<R>,
+        mut attributes: Attributes,
+        end: QName,
+    ) -> Result<Self, Error> {
+        let mut attr = IndexerAttr::default();
+        for a in attributes.with_checks(false).flatten() {
+            match a.key.as_ref() {
+                b"name" => attr.name = a.decode_and_unescape_value(reader)?.into_owned(),
+                b"value" => attr.value = a.decode_and_unescape_value(reader)?.into_owned(),
+                _ => {}
+            }
+        }
+        reader.read_to_end_into(end, &mut Vec::new())?;
+        Ok(attr)
+    }
+}
+
 impl Enclosure {
     fn from_xml<R: BufRead>(
         reader: &mut Reader<R>,
@@ -160,6 +274,40 @@ impl Enclosure {
     }
 }
 
+/// Parses sizes like "1.4 GiB" or "350.2 MiB" into bytes.
+pub fn parse_size(size: &str) -> Option<u64> {
+    let (number, unit) = size.trim().split_once(' ')?;
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit {
+        "B" | "Bytes" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
+/// Formats a size in bytes the way nyaa does, e.g. "1.4 GiB".
+pub fn format_size(size: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if size < 1 << 10 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
 pub fn element_text<R: BufRead>(reader: &mut Reader<R>) -> Result<String, Error> {
     let mut content: Option<String> = None;
     let mut buf = Vec::new();
@@ -196,6 +344,16 @@ mod test {
     use super::*;
     use std::io::Cursor;
 
+    #[test]
+    fn test_sizes() {
+        assert_eq!(parse_size("1.4 GiB"), Some(1503238554));
+        assert_eq!(parse_size("283.5 MiB"), Some(297271296));
+        assert_eq!(parse_size("lots"), None);
+        assert_eq!(format_size(1503238554), "1.4 GiB");
+        assert_eq!(format_size(297271296), "283.5 MiB");
+        assert_eq!(format_size(512), "512 B");
+    }
+
     #[test]
     fn test_read_from_xml() {
         let data = r#"<?xml version="1.0" encoding="utf-8"?>
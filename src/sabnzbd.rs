@@ -1,4 +1,5 @@
 use crate::curl;
+use crate::models::DownloadOptions;
 use serde::Deserialize;
 use url::Url;
 
@@ -24,6 +25,7 @@ impl From<curl::Error> for Error {
 pub struct SabnzbdClient {
     host: String,
     apikey: String,
+    dry_run: bool,
 }
 
 #[derive(Deserialize)]
@@ -51,9 +53,14 @@ impl SabnzbdClient {
         SabnzbdClient {
             host: host.to_string(),
             apikey: apikey.to_string(),
+            dry_run: false,
         }
     }
 
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
     fn base_url(&self) -> Result<Url, Error> {
         let url = Url::parse(&self.host)?;
         let mut url = url.join("api").unwrap();
@@ -64,12 +71,28 @@ impl SabnzbdClient {
     }
 
     // https://sabnzbd.org/wiki/advanced/api#addurl
-    pub fn addurl(&self, nzb_url: &str, cat: &str) -> Result<(), Error> {
+    pub fn addurl(&self, nzb_url: &str, options: &DownloadOptions) -> Result<(), Error> {
+        if self.dry_run {
+            println!("[dry-run] would queue {} with {:?}", nzb_url, options);
+            return Ok(());
+        }
         let mut url = self.base_url()?;
-        url.query_pairs_mut()
-            .append_pair("mode", "addurl")
-            .append_pair("name", nzb_url)
-            .append_pair("cat", cat);
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("mode", "addurl").append_pair("name", nzb_url);
+            if let Some(cat) = &options.category {
+                query.append_pair("cat", cat);
+            }
+            if let Some(priority) = options.priority {
+                query.append_pair("priority", &priority.to_string());
+            }
+            if let Some(pp) = options.pp {
+                query.append_pair("pp", &pp.to_string());
+            }
+            if let Some(script) = &options.script {
+                query.append_pair("script", script);
+            }
+        }
 
         let response = curl::get(url)?.json()?;
         handle_response(response)
@@ -0,0 +1,342 @@
+use crate::config::{SourceConfig, ToshoFormat};
+use crate::newznab::{self, NewznabClient};
+use crate::nyaa::{self, NyaaClient};
+use crate::rss;
+use crate::torznab::TorznabClient;
+use crate::tosho;
+
+#[derive(Debug)]
+pub enum Error {
+    ToshoError(tosho::Error),
+    NyaaError(nyaa::Error),
+    NewznabError(newznab::Error),
+}
+
+impl From<tosho::Error> for Error {
+    fn from(err: tosho::Error) -> Error {
+        Error::ToshoError(err)
+    }
+}
+
+impl From<nyaa::Error> for Error {
+    fn from(err: nyaa::Error) -> Error {
+        Error::NyaaError(err)
+    }
+}
+
+impl From<newznab::Error> for Error {
+    fn from(err: newznab::Error) -> Error {
+        Error::NewznabError(err)
+    }
+}
+
+/// Somewhere releases are found. Items carry their own nzb/torrent links, pub date and guid,
+/// any of which may be empty when the source doesn't provide them.
+pub trait Source {
+    /// Identifies the source in output and in the database, e.g. "animetosho"
+    fn name(&self) -> &str;
+
+    /// The latest releases, newest first
+    fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error>;
+
+    fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error>;
+
+    /// Searches for a single episode of a show. Sources that can search by season and episode
+    /// use `name`, the rest search `terms`, e.g. "SubsPlease Jujutsu Kaisen 08 1080p".
+    fn search_episode(
+        &self,
+        _name: &str,
+        _season: i32,
+        _episode: i32,
+        terms: &str,
+    ) -> Result<Vec<rss::Item>, Error> {
+        self.search(terms, Some(1))
+    }
+
+    /// Whether items carry nzb links. SABnzbd is all queue sends to, so sources that only
+    /// have torrents are left out of everything but search.
+    fn has_nzbs(&self) -> bool {
+        true
+    }
+}
+
+pub struct Animetosho {
+    format: ToshoFormat,
+}
+
+// tries the json feed first when it's configured, falling back to rss when it fails
+fn with_fallback<F, G>(format: ToshoFormat, json: F, rss: G) -> Result<Vec<rss::Item>, Error>
+where
+    F: FnOnce() -> Result<Vec<rss::Item>, tosho::Error>,
+    G: FnOnce() -> Result<Vec<rss::Item>, tosho::Error>,
+{
+    if format == ToshoFormat::Json {
+        match json() {
+            Ok(items) => return Ok(items),
+            Err(err) => eprintln!("animetosho json feed failed, falling back to rss: {:?}", err),
+        }
+    }
+    Ok(rss()?)
+}
+
+impl Source for Animetosho {
+    fn name(&self) -> &str {
+        "animetosho"
+    }
+
+    fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        with_fallback(self.format, || tosho::feed_json(&page), || tosho::feed(&page))
+    }
+
+    fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        with_fallback(
+            self.format,
+            || tosho::search_json(terms, page),
+            || tosho::search(terms, page),
+        )
+    }
+}
+
+impl Source for NyaaClient {
+    fn name(&self) -> &str {
+        "nyaa"
+    }
+
+    fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        Ok(NyaaClient::feed(self, page)?)
+    }
+
+    fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        Ok(NyaaClient::search(self, terms, page)?)
+    }
+
+    fn has_nzbs(&self) -> bool {
+        false
+    }
+}
+
+pub struct Newznab {
+    name: String,
+    client: NewznabClient,
+}
+
+impl Source for Newznab {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        Ok(self.client.feed(page)?)
+    }
+
+    fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        Ok(self.client.search(terms, page)?)
+    }
+
+    fn search_episode(
+        &self,
+        name: &str,
+        season: i32,
+        episode: i32,
+        terms: &str,
+    ) -> Result<Vec<rss::Item>, Error> {
+        if self.client.caps()?.supports_tv_search() {
+            Ok(self.client.tvsearch(name, season, episode, Some(1))?)
+        } else {
+            self.search(terms, Some(1))
+        }
+    }
+}
+
+pub struct Torznab {
+    name: String,
+    client: TorznabClient,
+}
+
+impl Source for Torznab {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        Ok(self.client.feed(page)?)
+    }
+
+    fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        Ok(self.client.search(terms, page)?)
+    }
+
+    fn search_episode(
+        &self,
+        name: &str,
+        season: i32,
+        episode: i32,
+        terms: &str,
+    ) -> Result<Vec<rss::Item>, Error> {
+        if self.client.caps()?.supports_tv_search() {
+            Ok(self.client.tvsearch(name, season, episode, Some(1))?)
+        } else {
+            self.search(terms, Some(1))
+        }
+    }
+
+    fn has_nzbs(&self) -> bool {
+        false
+    }
+}
+
+/// The configured sources, either the regular ones or only the fallbacks.
+pub fn from_config(configs: &[SourceConfig], fallback: bool) -> Vec<Box<dyn Source>> {
+    configs
+        .iter()
+        .filter(|config| config.is_fallback() == fallback)
+        .map(|config| -> Box<dyn Source> {
+            match config {
+                SourceConfig::Animetosho { format } => Box::new(Animetosho { format: *format }),
+                SourceConfig::Nyaa {
+                    url,
+                    category,
+                    trusted_only,
+                } => Box::new(NyaaClient::new(
+                    url.as_deref(),
+                    category.as_deref(),
+                    *trusted_only,
+                )),
+                SourceConfig::Newznab {
+                    name,
+                    url,
+                    apikey,
+                    categories,
+                    ..
+                } => Box::new(Newznab {
+                    name: name.clone(),
+                    client: NewznabClient::new(url, apikey, categories),
+                }),
+                SourceConfig::Torznab {
+                    name,
+                    url,
+                    apikey,
+                    categories,
+                    ..
+                } => Box::new(Torznab {
+                    name: name.clone(),
+                    client: TorznabClient::new(url, apikey, categories),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Drops the sources that only have torrents, for the commands that record nzb links.
+pub fn with_nzbs(sources: Vec<Box<dyn Source>>) -> Vec<Box<dyn Source>> {
+    sources.into_iter().filter(|source| source.has_nzbs()).collect()
+}
+
+/// Adds `items` to `merged`, keeping one item per title. Where a title is already there,
+/// any links it was missing are taken from the new item.
+pub fn merge_items(merged: &mut Vec<rss::Item>, items: Vec<rss::Item>) {
+    for item in items {
+        match merged.iter_mut().find(|m| m.title == item.title) {
+            Some(existing) => {
+                if existing.nzb_link.is_empty() {
+                    existing.nzb_link = item.nzb_link;
+                }
+                if existing.torrent_link.is_empty() {
+                    existing.torrent_link = item.torrent_link;
+                }
+            }
+            None => merged.push(item),
+        }
+    }
+}
+
+// runs `query` against every source, skipping those that fail unless they all do
+fn query_all<F>(sources: &[Box<dyn Source>], query: F) -> Result<Vec<rss::Item>, Error>
+where
+    F: Fn(&dyn Source) -> Result<Vec<rss::Item>, Error>,
+{
+    let mut merged = Vec::new();
+    let mut last_err = None;
+    let mut failed = 0;
+    for source in sources {
+        match query(source.as_ref()) {
+            Ok(items) => merge_items(&mut merged, items),
+            Err(err) => {
+                eprintln!("{} failed: {:?}", source.name(), err);
+                failed += 1;
+                last_err = Some(err);
+            }
+        }
+    }
+    match last_err {
+        Some(err) if failed == sources.len() => Err(err),
+        _ => Ok(merged),
+    }
+}
+
+/// Searches every source and merges the results.
+pub fn search_all(
+    sources: &[Box<dyn Source>],
+    terms: &str,
+    page: Option<u8>,
+) -> Result<Vec<rss::Item>, Error> {
+    query_all(sources, |source| source.search(terms, page))
+}
+
+/// Searches every source for a single episode and merges the results.
+pub fn search_episode_all(
+    sources: &[Box<dyn Source>],
+    name: &str,
+    season: i32,
+    episode: i32,
+    terms: &str,
+) -> Result<Vec<rss::Item>, Error> {
+    query_all(sources, |source| {
+        source.search_episode(name, season, episode, terms)
+    })
+}
+
+/// Gets a page of every source's feed and merges the results.
+pub fn feed_all(sources: &[Box<dyn Source>], page: u8) -> Result<Vec<rss::Item>, Error> {
+    query_all(sources, |source| source.feed(page))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn item(title: &str, nzb_link: &str, torrent_link: &str) -> rss::Item {
+        rss::Item {
+            title: title.to_string(),
+            nzb_link: nzb_link.to_string(),
+            torrent_link: torrent_link.to_string(),
+            ..rss::Item::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_items() {
+        let mut merged = Vec::new();
+        merge_items(
+            &mut merged,
+            vec![
+                item("[Judas] Dorohedoro - 08 [1080p]", "", "a.torrent"),
+                item("[Judas] Dorohedoro - 09 [1080p]", "b.nzb", ""),
+            ],
+        );
+        merge_items(
+            &mut merged,
+            vec![
+                item("[Judas] Dorohedoro - 08 [1080p]", "a.nzb", "other.torrent"),
+                item("[Judas] Dorohedoro - 10 [1080p]", "", "c.torrent"),
+            ],
+        );
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].nzb_link, "a.nzb");
+        assert_eq!(merged[0].torrent_link, "a.torrent");
+        assert_eq!(merged[1].nzb_link, "b.nzb");
+        assert_eq!(merged[2].title, "[Judas] Dorohedoro - 10 [1080p]");
+    }
+}
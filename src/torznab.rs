@@ -0,0 +1,190 @@
+use crate::newznab::{Caps, Error, NewznabClient};
+use crate::rss;
+
+/// A client for the Torznab api served by Jackett and Prowlarr. Torznab is the Newznab api
+/// for torrent indexers, so the requests are the same and only the results differ, carrying
+/// seeders, info hashes and magnet links in `torznab:attr`s.
+pub struct TorznabClient {
+    client: NewznabClient,
+}
+
+impl TorznabClient {
+    /// `url` is the indexer's torznab endpoint without the trailing "api", e.g.
+    /// "http://localhost:9696/1/" for Prowlarr or
+    /// "http://localhost:9117/api/v2.0/indexers/all/results/torznab/" for Jackett.
+    pub fn new(url: &str, apikey: &str, categories: &[String]) -> TorznabClient {
+        TorznabClient {
+            client: NewznabClient::new(url, apikey, categories),
+        }
+    }
+
+    pub fn caps(&self) -> Result<&Caps, Error> {
+        self.client.caps()
+    }
+
+    pub fn search(&self, terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+        self.client.search(terms, page)
+    }
+
+    pub fn feed(&self, page: u8) -> Result<Vec<rss::Item>, Error> {
+        self.client.feed(page)
+    }
+
+    pub fn tvsearch(
+        &self,
+        name: &str,
+        season: i32,
+        episode: i32,
+        page: Option<u8>,
+    ) -> Result<Vec<rss::Item>, Error> {
+        self.client.tvsearch(name, season, episode, page)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::newznab::{read_caps, read_results};
+
+    #[test]
+    fn test_read_caps() {
+        // recorded from Prowlarr
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<caps>
+  <server title="Prowlarr" />
+  <limits default="100" max="100" />
+  <searching>
+    <search available="yes" supportedParams="q" />
+    <tv-search available="yes" supportedParams="q,season,ep" />
+    <movie-search available="no" supportedParams="q" />
+    <music-search available="no" supportedParams="q" />
+    <audio-search available="no" supportedParams="q" />
+    <book-search available="no" supportedParams="q" />
+  </searching>
+  <categories>
+    <category id="5000" name="TV">
+      <subcat id="5070" name="TV/Anime" />
+    </category>
+    <category id="100001" name="Anime - English-translated" />
+  </categories>
+  <tags />
+</caps>
+"#;
+        let caps = read_caps(data.as_bytes()).unwrap();
+        assert!(caps.supports_tv_search());
+        assert_eq!(caps.categories.len(), 2);
+        assert_eq!(caps.categories[0].subcats[0].id, "5070");
+        assert_eq!(caps.categories[1].id, "100001");
+
+        // Jackett's tv-search without season and ep
+        let data = r#"<caps><searching><tv-search available="yes" supportedParams="q"/></searching></caps>"#;
+        assert!(!read_caps(data.as_bytes()).unwrap().supports_tv_search());
+    }
+
+    #[test]
+    fn test_torznab_attrs() {
+        // Prowlarr orders peers before seeders and uppercases info hashes
+        let data = r#"<rss version="2.0" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+  <channel>
+    <item>
+      <title>[Judas] Dorohedoro - 09 [1080p][HEVC x265 10bit][Eng-Subs]</title>
+      <guid>https://nyaa.si/view/1227240</guid>
+      <pubDate>Sun, 08 Mar 2020 08:56:16 +0000</pubDate>
+      <enclosure url="http://localhost:9696/1/download?file=a" length="0" type="application/x-bittorrent" />
+      <torznab:attr name="size" value="297271296" />
+      <torznab:attr name="peers" value="12" />
+      <torznab:attr name="seeders" value="12" />
+      <torznab:attr name="infohash" value="C9132CCB1D4D0BC5F29E1064143A96E468CF2870" />
+    </item>
+    <item>
+      <title>[Judas] Dorohedoro - 10 [1080p][HEVC x265 10bit][Eng-Subs]</title>
+      <pubDate>Sun, 15 Mar 2020 08:56:16 +0000</pubDate>
+      <torznab:attr name="seeders" value="3" />
+      <torznab:attr name="leechers" value="5" />
+      <torznab:attr name="magneturl" value="magnet:?xt=urn:btih:abc" />
+    </item>
+  </channel>
+</rss>
+"#;
+        let items = read_results(data.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+
+        let item = &items[0];
+        assert_eq!(item.torrent_link, "http://localhost:9696/1/download?file=a");
+        assert_eq!(item.nzb_link, "");
+        assert_eq!(item.size, Some(297271296));
+        assert_eq!(item.seeders, Some(12));
+        assert_eq!(item.leechers, Some(0));
+        assert_eq!(
+            item.info_hash.as_deref(),
+            Some("c9132ccb1d4d0bc5f29e1064143a96e468cf2870")
+        );
+
+        let item = &items[1];
+        assert_eq!(item.seeders, Some(3));
+        assert_eq!(item.leechers, Some(5));
+        assert_eq!(item.magnet_link.as_deref(), Some("magnet:?xt=urn:btih:abc"));
+        assert_eq!(item.info_hash, None);
+    }
+
+    #[test]
+    fn test_read_results() {
+        // recorded from Jackett, searching nyaa
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+  <channel>
+    <atom:link href="http://127.0.0.1:9117/" rel="self" type="application/rss+xml" />
+    <title>Nyaa.si</title>
+    <description>Nyaa.si is a Public torrent site for anime</description>
+    <link>https://nyaa.si/</link>
+    <language>en-US</language>
+    <category>search</category>
+    <item>
+      <title>[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv</title>
+      <guid>https://nyaa.si/view/1300519</guid>
+      <jackettindexer id="nyaasi">Nyaa.si</jackettindexer>
+      <type>public</type>
+      <comments>https://nyaa.si/view/1300519</comments>
+      <pubDate>Sat, 14 Nov 2020 15:32:03 +0000</pubDate>
+      <size>1503238554</size>
+      <grabs>25361</grabs>
+      <description />
+      <link>http://127.0.0.1:9117/dl/nyaasi/?jackett_apikey=secret&amp;path=abc&amp;file=%5BSubsPlease%5D+Jujutsu+Kaisen+-+08</link>
+      <category>5070</category>
+      <category>100001</category>
+      <enclosure url="http://127.0.0.1:9117/dl/nyaasi/?jackett_apikey=secret&amp;path=abc&amp;file=%5BSubsPlease%5D+Jujutsu+Kaisen+-+08" length="1503238554" type="application/x-bittorrent" />
+      <torznab:attr name="category" value="5070" />
+      <torznab:attr name="category" value="100001" />
+      <torznab:attr name="seeders" value="2311" />
+      <torznab:attr name="peers" value="2349" />
+      <torznab:attr name="infohash" value="A0BBDB2BD3E1BED4E2BFC20BD0A5FF0A6D8F4D43" />
+      <torznab:attr name="magneturl" value="magnet:?xt=urn:btih:a0bbdb2bd3e1bed4e2bfc20bd0a5ff0a6d8f4d43&amp;dn=%5BSubsPlease%5D+Jujutsu+Kaisen+-+08" />
+      <torznab:attr name="downloadvolumefactor" value="0" />
+      <torznab:attr name="uploadvolumefactor" value="1" />
+    </item>
+  </channel>
+</rss>
+"#;
+        let items = read_results(data.as_bytes()).unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.title, "[SubsPlease] Jujutsu Kaisen - 08 (1080p) [E2508E65].mkv");
+        assert_eq!(item.nzb_link, "");
+        assert!(item
+            .torrent_link
+            .starts_with("http://127.0.0.1:9117/dl/nyaasi/?jackett_apikey=secret&path=abc"));
+        assert_eq!(item.guid, "https://nyaa.si/view/1300519");
+        assert_eq!(item.size, Some(1503238554));
+        assert_eq!(item.seeders, Some(2311));
+        assert_eq!(item.leechers, Some(38));
+        assert_eq!(
+            item.info_hash.as_deref(),
+            Some("a0bbdb2bd3e1bed4e2bfc20bd0a5ff0a6d8f4d43")
+        );
+        assert_eq!(
+            item.magnet_link.as_deref(),
+            Some("magnet:?xt=urn:btih:a0bbdb2bd3e1bed4e2bfc20bd0a5ff0a6d8f4d43&dn=%5BSubsPlease%5D+Jujutsu+Kaisen+-+08")
+        );
+        assert_eq!(item.pub_date.to_string(), "2020-11-14 15:32:03");
+    }
+}
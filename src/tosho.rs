@@ -1,13 +1,18 @@
 use crate::curl;
 use crate::rss;
+use chrono::DateTime;
+use serde::Deserialize;
 
 const ANIMETOSHO_RSS_URL: &str = "https://feed.animetosho.org/rss2";
+const ANIMETOSHO_JSON_URL: &str = "https://feed.animetosho.org/json";
 
 #[derive(Debug)]
 pub enum Error {
     Io(::std::io::Error),
     CurlError(curl::Error),
     RssError(rss::Error),
+    JsonError(serde_json::Error),
+    BadTimestamp(i64),
 }
 
 impl From<::std::io::Error> for Error {
@@ -28,6 +33,60 @@ impl From<rss::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::JsonError(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonItem {
+    id: u64,
+    title: String,
+    link: String,
+    timestamp: i64,
+    status: Option<String>,
+    torrent_url: Option<String>,
+    info_hash: Option<String>,
+    magnet_uri: Option<String>,
+    seeders: Option<u32>,
+    leechers: Option<u32>,
+    nzb_url: Option<String>,
+    total_size: Option<u64>,
+    num_files: Option<u32>,
+    anidb_aid: Option<u32>,
+    anidb_eid: Option<u32>,
+    anidb_fid: Option<u32>,
+}
+
+impl JsonItem {
+    fn into_item(self) -> Result<rss::Item, Error> {
+        let pub_date = DateTime::from_timestamp(self.timestamp, 0)
+            .ok_or(Error::BadTimestamp(self.timestamp))?
+            .naive_utc();
+        Ok(rss::Item {
+            title: self.title,
+            link: self.link,
+            nzb_link: self.nzb_url.unwrap_or_default(),
+            torrent_link: self.torrent_url.unwrap_or_default(),
+            pub_date,
+            // the same as the rss feed's guid, "https://animetosho.org/view/a343174"
+            guid: format!("a{}", self.id),
+            size: self.total_size,
+            info_hash: self.info_hash,
+            magnet_link: self.magnet_uri,
+            seeders: self.seeders,
+            leechers: self.leechers,
+            file_count: self.num_files,
+            anidb_aid: self.anidb_aid,
+            anidb_eid: self.anidb_eid,
+            anidb_fid: self.anidb_fid,
+            status: self.status,
+            ..rss::Item::default()
+        })
+    }
+}
+
 pub fn search(terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
     let query = terms.split(' ').collect::<Vec<&str>>().join("+");
     let page = page.unwrap_or(1);
@@ -51,3 +110,119 @@ fn results_from_url(url: &str) -> Result<Vec<rss::Item>, Error> {
     let items = rss::read_from(&response.body[..])?;
     Ok(items)
 }
+
+pub fn search_json(terms: &str, page: Option<u8>) -> Result<Vec<rss::Item>, Error> {
+    let query = terms.split(' ').collect::<Vec<&str>>().join("+");
+    let page = page.unwrap_or(1);
+    let url = &[
+        ANIMETOSHO_JSON_URL,
+        "?q=",
+        &query,
+        "&page=",
+        &page.to_string(),
+    ]
+    .join("");
+    results_from_json_url(url)
+}
+
+pub fn feed_json(page: &u8) -> Result<Vec<rss::Item>, Error> {
+    results_from_json_url(&[ANIMETOSHO_JSON_URL, "?page=", &page.to_string()].join(""))
+}
+
+fn results_from_json_url(url: &str) -> Result<Vec<rss::Item>, Error> {
+    let response = curl::get(url)?;
+    read_json(&response.body)
+}
+
+fn read_json(body: &[u8]) -> Result<Vec<rss::Item>, Error> {
+    let items: Vec<JsonItem> = serde_json::from_slice(body)?;
+    items.into_iter().map(JsonItem::into_item).collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_read_json() {
+        let data = r#"[
+  {
+    "id": 344016,
+    "title": "[Judas] Dorohedoro - 09 [1080p][HEVC x265 10bit][Eng-Subs]",
+    "link": "https://animetosho.org/view/judas-dorohedoro-09-1080p-hevc-x265-10bit-eng-subs.n1227240",
+    "timestamp": 1583657776,
+    "status": "complete",
+    "tosho_id": null,
+    "nyaa_id": 1227240,
+    "nyaa_subdom": null,
+    "anidex_id": null,
+    "torrent_url": "https://animetosho.org/storage/torrent/c9132ccb1d4d0bc5f29e1064143a96e468cf2870/%5BJudas%5D%20Dorohedoro%20-%2009.torrent",
+    "torrent_name": "[Judas] Dorohedoro - 09 [1080p][HEVC x265 10bit][Eng-Subs].mkv",
+    "info_hash": "c9132ccb1d4d0bc5f29e1064143a96e468cf2870",
+    "info_hash_v2": null,
+    "magnet_uri": "magnet:?xt=urn:btih:ZEJSZSY5JUF4L4U6CBSBIOUW4RUM6KDQ&tr=http%3A%2F%2Fnyaa.tracker.wf%3A7777%2Fannounce",
+    "seeders": 12,
+    "leechers": 0,
+    "torrent_downloaded_count": 4021,
+    "tracker_updated": 1583700000,
+    "nzb_url": "https://animetosho.org/storage/nzbs/00053fd0/%5BJudas%5D%20Dorohedoro%20-%2009.nzb",
+    "total_size": 297271296,
+    "num_files": 1,
+    "anidb_aid": 14807,
+    "anidb_eid": 225612,
+    "anidb_fid": null,
+    "article_url": null,
+    "article_title": null,
+    "website_url": "https://github.com/Judas-Raws"
+  },
+  {
+    "id": 344017,
+    "title": "[SubsPlease] Jujutsu Kaisen (01-24) (1080p) [Batch]",
+    "link": "https://animetosho.org/view/subsplease-jujutsu-kaisen-01-24-1080p-batch.n1350000",
+    "timestamp": 1616800000,
+    "status": "processing",
+    "torrent_url": "https://animetosho.org/storage/torrent/0000/batch.torrent",
+    "info_hash": null,
+    "magnet_uri": null,
+    "seeders": null,
+    "leechers": null,
+    "nzb_url": null,
+    "total_size": 34359738368,
+    "num_files": 24,
+    "anidb_aid": null,
+    "anidb_eid": null,
+    "anidb_fid": null
+  }
+]"#;
+        let items = read_json(data.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+
+        let item = &items[0];
+        assert_eq!(
+            item.title,
+            "[Judas] Dorohedoro - 09 [1080p][HEVC x265 10bit][Eng-Subs]"
+        );
+        assert_eq!(item.guid, "a344016");
+        assert_eq!(item.pub_date.to_string(), "2020-03-08 08:56:16");
+        assert_eq!(
+            item.nzb_link,
+            "https://animetosho.org/storage/nzbs/00053fd0/%5BJudas%5D%20Dorohedoro%20-%2009.nzb"
+        );
+        assert_eq!(item.size, Some(297271296));
+        assert_eq!(item.file_count, Some(1));
+        assert_eq!(
+            item.info_hash.as_deref(),
+            Some("c9132ccb1d4d0bc5f29e1064143a96e468cf2870")
+        );
+        assert_eq!(item.anidb_aid, Some(14807));
+        assert_eq!(item.anidb_eid, Some(225612));
+        assert_eq!(item.anidb_fid, None);
+        assert_eq!(item.status.as_deref(), Some("complete"));
+
+        let item = &items[1];
+        assert_eq!(item.nzb_link, "");
+        assert_eq!(item.file_count, Some(24));
+        assert_eq!(item.status.as_deref(), Some("processing"));
+    }
+}
@@ -1,8 +1,170 @@
-use crate::models::{Episode, Quality};
+use crate::config::{Brackets, GroupGrammar, QualityLocation};
+use crate::models::{Episode, EpisodeKind, EpisodeRange, Quality, ReleaseAttributes};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
-fn match_name_ep_version(text: &str) -> Option<(String, i32, i32)> {
-    let mut rsn = text.rsplitn(2, " - ").map(str::trim);
+static GRAMMARS: OnceLock<Vec<TitleGrammar>> = OnceLock::new();
+
+/// How titles from one release group are laid out, see `GroupGrammar` for the config side.
+pub struct TitleGrammar {
+    group: String,
+    brackets: Brackets,
+    quality: QualityLocation,
+    separator: String,
+    skip: Vec<String>,
+    regex: Option<Regex>,
+}
+
+impl TitleGrammar {
+    /// Starts from the built-in rules for the group, if there are any, and overrides only what
+    /// the config sets.
+    pub fn new(group: &str, grammar: &GroupGrammar) -> Result<TitleGrammar, regex::Error> {
+        let builtin = builtin_grammars()
+            .into_iter()
+            .find(|builtin| builtin.group == group)
+            .unwrap_or_else(|| TitleGrammar::builtin(group));
+        Ok(TitleGrammar {
+            brackets: grammar.brackets.unwrap_or(builtin.brackets),
+            quality: grammar.quality.unwrap_or(builtin.quality),
+            separator: grammar.separator.clone().unwrap_or(builtin.separator),
+            skip: grammar.skip.clone().unwrap_or(builtin.skip),
+            regex: match grammar.regex.as_deref() {
+                Some(regex) => Some(Regex::new(regex)?),
+                None => builtin.regex,
+            },
+            group: builtin.group,
+        })
+    }
+
+    fn builtin(group: &str) -> TitleGrammar {
+        TitleGrammar {
+            group: group.to_string(),
+            brackets: Brackets::Square,
+            quality: QualityLocation::First,
+            separator: " - ".to_string(),
+            skip: vec!["VRV".to_string(), "WEB".to_string()],
+            regex: None,
+        }
+    }
+
+    fn delimiters(&self) -> (&'static str, &'static str, char) {
+        match self.brackets {
+            Brackets::Square => ("[", "]", ']'),
+            Brackets::Round => ("(", ")", ')'),
+        }
+    }
+}
+
+fn builtin_grammars() -> Vec<TitleGrammar> {
+    ["SubsPlease", "PAS"]
+        .iter()
+        .map(|group| TitleGrammar {
+            brackets: Brackets::Round,
+            quality: QualityLocation::Last,
+            ..TitleGrammar::builtin(group)
+        })
+        .collect()
+}
+
+/// Installs the per-group rules from tosho.toml, which take precedence over the built-in ones.
+pub fn set_group_grammars(groups: &HashMap<String, GroupGrammar>) -> Result<(), regex::Error> {
+    let mut grammars = groups
+        .iter()
+        .map(|(group, grammar)| TitleGrammar::new(group, grammar))
+        .collect::<Result<Vec<_>, _>>()?;
+    grammars.extend(builtin_grammars());
+    let _ = GRAMMARS.set(grammars);
+    Ok(())
+}
+
+// keywords used in place of an episode number, longest first so "SPECIAL" wins over "SP"
+const EPISODE_KEYWORDS: &[(&str, EpisodeKind)] = &[
+    ("SPECIAL", EpisodeKind::Special),
+    ("RECAP", EpisodeKind::Recap),
+    ("NCOP", EpisodeKind::CreditlessOp),
+    ("NCED", EpisodeKind::CreditlessEd),
+    ("OVA", EpisodeKind::Ova),
+    ("OAD", EpisodeKind::Ova),
+    ("SP", EpisodeKind::Special),
+];
+
+/// Parses the digits after the point of an episode like "12.5" into hundredths, so "5" is 50
+/// and "05" is 5. Finer fractions than that aren't supported.
+pub fn parse_part(text: &str) -> Option<i32> {
+    if text.is_empty() || text.len() > 2 || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let part = text.parse::<i32>().ok()?;
+    Some(if text.len() == 1 { part * 10 } else { part })
+}
+
+fn match_episode_number(text: &str) -> Option<(i32, EpisodeKind, Option<i32>)> {
+    if let Ok(ep) = text.parse::<i32>() {
+        return Some((ep, EpisodeKind::Regular, None));
+    }
+    if let Some((ep, part)) = text.split_once('.') {
+        return match (ep.parse::<i32>(), parse_part(part)) {
+            (Ok(ep), Some(part)) => Some((ep, EpisodeKind::Special, Some(part))),
+            _ => None,
+        };
+    }
+    let upper = text.to_uppercase();
+    for (keyword, kind) in EPISODE_KEYWORDS {
+        if let Some(rest) = upper.strip_prefix(keyword) {
+            let rest = rest.trim();
+            if rest.is_empty() {
+                return Some((1, *kind, None));
+            }
+            return rest.parse::<i32>().ok().map(|ep| (ep, *kind, None));
+        }
+    }
+    None
+}
+
+fn match_episode_range(text: &str) -> Option<EpisodeRange> {
+    let (first, last) = text.split_once(['-', '~'])?;
+    let first = first.trim().parse::<i32>().ok()?;
+    let last = last.trim().parse::<i32>().ok()?;
+    if first >= last {
+        return None;
+    }
+    Some(EpisodeRange {
+        first,
+        last: Some(last),
+    })
+}
+
+// matches multi-episode numbers like "01+02" or "01 & 02"
+fn match_episode_list(text: &str) -> Option<Vec<i32>> {
+    if !text.contains(['+', '&']) {
+        return None;
+    }
+    let mut episodes: Vec<i32> = Vec::new();
+    for ep in text.split(['+', '&']).map(str::trim) {
+        if ep.is_empty() || !ep.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let ep = ep.parse().ok()?;
+        if episodes.last().is_some_and(|&last| ep <= last) {
+            return None;
+        }
+        episodes.push(ep);
+    }
+    Some(episodes)
+}
+
+fn match_name_ep_version(
+    text: &str,
+    separator: &str,
+) -> Option<(String, Vec<i32>, EpisodeKind, Option<i32>, Option<EpisodeRange>, i32)> {
+    let mut rsn = text.rsplitn(2, separator).map(str::trim);
     match rsn.next() {
         Some(epv) => {
             let epv = if epv.ends_with(" END") {
@@ -11,10 +173,16 @@ fn match_name_ep_version(text: &str) -> Option<(String, i32, i32)> {
                 epv
             };
             let mut sn = epv.splitn(2, 'v').map(str::trim);
-            let ep: i32 = match sn.next() {
-                Some(ep) => match ep.parse::<i32>() {
-                    Ok(ep) => ep,
-                    Err(_) => return None,
+            let (episodes, kind, part, range) = match sn.next() {
+                Some(ep) => match match_episode_range(ep) {
+                    Some(range) => (vec![range.first], EpisodeKind::Regular, None, Some(range)),
+                    None => match match_episode_list(ep) {
+                        Some(episodes) => (episodes, EpisodeKind::Regular, None, None),
+                        None => {
+                            let (ep, kind, part) = match_episode_number(ep)?;
+                            (vec![ep], kind, part, None)
+                        }
+                    },
                 },
                 None => return None,
             };
@@ -25,91 +193,665 @@ fn match_name_ep_version(text: &str) -> Option<(String, i32, i32)> {
                 },
                 None => 1,
             };
-            rsn.next().map(|name| (name.to_string(), ep, v))
+            rsn.next().map(|name| (name.to_string(), episodes, kind, part, range, v))
         }
         None => None,
     }
 }
 
-fn match_name_sxxexx(text: &str) -> Option<(String, i32, i32, i32)> {
+fn match_name_sxxexx(text: &str) -> Option<(String, i32, Vec<i32>, i32)> {
     let mut rsn = text.trim().rsplitn(2, ' ').map(str::trim);
-    match rsn.next() {
-        Some(part) if part.len() == 6 && part.chars().count() == 6 => {
-            let mut chars = part.chars();
-            match (
-                chars.next().unwrap(),
-                chars.next().unwrap(),
-                chars.next().unwrap(),
-                chars.next().unwrap(),
-                chars.next().unwrap(),
-                chars.next().unwrap(),
-            ) {
-                ('S', s1, s2, 'E', e1, e2)
-                    if s1.is_numeric() && s2.is_numeric() && e1.is_numeric() && e2.is_numeric() =>
-                {
-                    let season = match part[1..3].parse() {
-                        Ok(season) => season,
-                        Err(_) => return None,
-                    };
-                    let ep = match part[4..].parse() {
-                        Ok(ep) => ep,
-                        Err(_) => return None,
-                    };
-                    rsn.next()
-                        .map(|name| (name.trim_end_matches('-').trim_end().to_string(), season, ep, 1))
+    let (season, episodes) = match_sxxexx(rsn.next()?)?;
+    rsn.next().map(|name| {
+        let name = name.trim_end_matches('-').trim_end().to_string();
+        (name, season, episodes, 1)
+    })
+}
+
+// steps over tags like "[VRV]" or the "WEB" in "(WEB 1080 AAC)" that come before the quality
+fn skip_tags(title: &str, qidx: usize, skip: &[String], sqc: &str, eqc: char) -> usize {
+    let rest = &title[qidx + 1..];
+    for tag in skip {
+        if let Some(after) = rest.strip_prefix(tag.as_str()) {
+            if after.starts_with(eqc) {
+                let next = qidx + 2 + tag.len();
+                if let Some(nidx) = title[next..].find(sqc) {
+                    return next + nidx;
                 }
-                _ => None,
+            } else if after.starts_with(' ') {
+                return qidx + tag.len() + 1;
             }
         }
+    }
+    qidx
+}
+
+// finds a checksum tag like "[E2508E65]" or "(57023320)"
+fn match_crc32(text: &str) -> Option<u32> {
+    text.match_indices(['[', '(']).find_map(|(idx, _)| {
+        let tag = text.get(idx + 1..idx + 10)?;
+        let (hex, close) = (tag.get(..8)?, tag.get(8..)?);
+        if (close == "]" || close == ")") && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            u32::from_str_radix(hex, 16).ok()
+        } else {
+            None
+        }
+    })
+}
+
+fn bracket_contents(text: &str) -> Vec<&str> {
+    let mut contents = Vec::new();
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '[' | '(' => start = Some(idx + 1),
+            ']' | ')' => {
+                if let Some(start) = start.take() {
+                    contents.push(&text[start..idx]);
+                }
+            }
+            _ => {}
+        }
+    }
+    contents
+}
+
+fn match_audio_codec(word: &str) -> Option<&'static str> {
+    // drop channel layouts like the "2.0" in "AAC2.0"
+    let codec = word.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match codec {
+        "AAC" => Some("AAC"),
+        "DDP" | "DD+" | "EAC" | "E-AC-" => Some("DDP"),
+        "AC" | "DD" => Some("AC3"),
+        "FLAC" => Some("FLAC"),
+        "OPUS" => Some("Opus"),
+        "DTS" => Some("DTS"),
+        "MP" => Some("MP3"),
         _ => None,
     }
 }
 
+fn match_attribute_word(attributes: &mut ReleaseAttributes, word: &str) {
+    let upper = word.to_uppercase();
+    match upper.as_str() {
+        "HEVC" | "X265" | "H265" | "H.265" => attributes.video_codec = Some("HEVC".to_string()),
+        "AVC" | "X264" | "H264" | "H.264" => attributes.video_codec = Some("AVC".to_string()),
+        "AV1" => attributes.video_codec = Some("AV1".to_string()),
+        "10BIT" | "10-BIT" | "HI10" | "HI10P" => attributes.bit_depth = Some(10),
+        "8BIT" | "8-BIT" => attributes.bit_depth = Some(8),
+        "WEB" | "WEBRIP" | "WEB-DL" => attributes.source = Some("WEB".to_string()),
+        "BD" | "BDRIP" | "BLURAY" | "BLU-RAY" => attributes.source = Some("BD".to_string()),
+        "DVD" | "DVDRIP" => attributes.source = Some("DVD".to_string()),
+        "TV" | "HDTV" => attributes.source = Some("TV".to_string()),
+        "RAW" => attributes.raw = true,
+        "UNCENSORED" | "UNCEN" => attributes.uncensored = true,
+        _ => {
+            if let Some(lang) = strip_subs_suffix(word) {
+                let lang = if lang.eq_ignore_ascii_case("multi")
+                    || lang.eq_ignore_ascii_case("multiple")
+                {
+                    "Multi".to_string()
+                } else {
+                    lang.to_string()
+                };
+                attributes.subtitles.push(lang);
+            } else if let Some(codec) = match_audio_codec(&upper) {
+                attributes.audio_codec = Some(codec.to_string());
+            }
+        }
+    }
+}
+
+// the language of a tag like "Eng-Subs" or "eng-sub", cut from `word` itself since
+// uppercasing can change the length of non-ascii text
+fn strip_subs_suffix(word: &str) -> Option<&str> {
+    ["-subs", "-sub"].iter().find_map(|suffix| {
+        let split = word.len().checked_sub(suffix.len())?;
+        if !word.is_char_boundary(split) {
+            return None;
+        }
+        let (lang, tail) = word.split_at(split);
+        tail.eq_ignore_ascii_case(suffix).then_some(lang)
+    })
+}
+
+// reads codec, source, audio and subtitle tags from the bracketed parts of a title
+fn match_attributes(text: &str) -> ReleaseAttributes {
+    let mut attributes = ReleaseAttributes::default();
+    for content in bracket_contents(text) {
+        if content.eq_ignore_ascii_case("multiple subtitle") {
+            attributes.subtitles.push("Multi".to_string());
+            continue;
+        }
+        for word in content.split_whitespace() {
+            match_attribute_word(&mut attributes, word);
+        }
+    }
+    attributes
+}
+
+/// Computes the CRC32 of a file, to compare against the checksum from its release title.
+pub fn file_crc32(path: &Path) -> io::Result<u32> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher.finalize())
+}
+
+fn is_batch_title(title: &str) -> bool {
+    let upper = title.to_uppercase();
+    upper.contains("[BATCH]") || upper.contains("(BATCH)")
+}
+
+fn parse_season_marker(marker: &str) -> Option<i32> {
+    let marker = marker.trim();
+    let number = marker
+        .strip_prefix("Season ")
+        .or_else(|| marker.strip_prefix('S'))?;
+    number.trim().parse().ok()
+}
+
+// splits "Dorohedoro (Season 1)", "Dorohedoro Season 1" or "Dorohedoro S01" into name and season
+fn match_name_season(text: &str) -> Option<(String, i32)> {
+    let text = text.trim();
+    if let Some((name, marker)) = text.strip_suffix(')').and_then(|t| t.rsplit_once('(')) {
+        return parse_season_marker(marker).map(|season| (name.trim().to_string(), season));
+    }
+    if let Some(idx) = text.rfind(" Season ") {
+        return parse_season_marker(&text[idx..])
+            .map(|season| (text[..idx].trim().to_string(), season));
+    }
+    let (name, marker) = text.rsplit_once(' ')?;
+    parse_season_marker(marker).map(|season| (name.trim().to_string(), season))
+}
+
+const ROMAN_SEASONS: &[(&str, i32)] = &[
+    ("II", 2),
+    ("III", 3),
+    ("IV", 4),
+    ("V", 5),
+    ("VI", 6),
+    ("VII", 7),
+    ("VIII", 8),
+    ("IX", 9),
+    ("X", 10),
+];
+
+fn parse_ordinal(text: &str) -> Option<i32> {
+    let number = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| text.to_lowercase().strip_suffix(suffix).map(str::to_string))?;
+    number.parse().ok()
+}
+
+/// Splits a season marker off the end of a show name, so "Beastars S2", "Beastars Season 2",
+/// "Beastars 2nd Season" and "Beastars II" all become ("Beastars", 2).
+pub fn normalize_season(name: &str) -> Option<(String, i32)> {
+    let name = name.trim();
+    if let Some((base, season)) = match_name_season(name) {
+        return Some((base, season)).filter(|(base, season)| !base.is_empty() && *season > 0);
+    }
+    let (base, last) = name.rsplit_once(' ')?;
+    let (base, season) = if last.eq_ignore_ascii_case("season") {
+        let (base, ordinal) = base.rsplit_once(' ')?;
+        (base, parse_ordinal(ordinal)?)
+    } else {
+        let (_, season) = ROMAN_SEASONS.iter().find(|(roman, _)| *roman == last)?;
+        (base, *season)
+    };
+    let base = base.trim();
+    if base.is_empty() {
+        return None;
+    }
+    Some((base.to_string(), season))
+}
+
+// matches the name of a batch release in title[start..qidx], returning the index of the
+// quality bracket, which moves past the range when it has its own, e.g. "Show (01-12) (1080p)"
+fn match_name_batch(
+    title: &str,
+    start: usize,
+    qidx: usize,
+    sqc: &str,
+    eqc: &str,
+) -> Option<(String, Option<i32>, EpisodeRange, usize)> {
+    let text = title[start..qidx].trim();
+    // the range may also close out the name when the quality is found from the end
+    let trailing = text
+        .strip_suffix(eqc)
+        .and_then(|text| text.rsplit_once(sqc))
+        .and_then(|(name, range)| match_episode_range(range).map(|range| (name, range)));
+    let text = trailing.map_or(text, |(name, _)| name);
+    let (name, season) = match match_name_season(text) {
+        Some((name, season)) => (name, Some(season)),
+        None => (text.trim().to_string(), None),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    if let Some((_, range)) = trailing {
+        return Some((name, season, range, qidx));
+    }
+    let ranged = title[qidx + 1..].find(eqc).and_then(|reidx| {
+        let range = match_episode_range(&title[qidx + 1..qidx + 1 + reidx])?;
+        let next = qidx + 2 + reidx;
+        title[next..].find(sqc).map(|nidx| (range, next + nidx))
+    });
+    let (range, qidx) = ranged.unwrap_or((
+        EpisodeRange {
+            first: 1,
+            last: None,
+        },
+        qidx,
+    ));
+    Some((name, season, range, qidx))
+}
+
+fn match_title_regex(title: &str, group: String, regex: &Regex) -> Option<Episode> {
+    let caps = regex.captures(title)?;
+    let name = caps.name("name")?.as_str().trim().to_string();
+    let episode = caps.name("episode")?.as_str().trim();
+    let (episode, kind, part, range) = match match_episode_range(episode) {
+        Some(range) => (range.first, EpisodeKind::Regular, None, Some(range)),
+        None => {
+            let (episode, kind, part) = match_episode_number(episode)?;
+            (episode, kind, part, None)
+        }
+    };
+    let season = match caps.name("season") {
+        Some(season) => Some(season.as_str().parse().ok()?),
+        None => None,
+    };
+    let version = match caps.name("version") {
+        Some(version) => version.as_str().parse().ok()?,
+        None => 1,
+    };
+    Some(Episode {
+        group,
+        name,
+        season,
+        episode,
+        episodes: vec![episode],
+        kind,
+        part,
+        range,
+        version,
+        quality: caps
+            .name("quality")
+            .and_then(|quality| Quality::from_str(quality.as_str()).ok()),
+        crc32: caps
+            .name("crc32")
+            .and_then(|crc32| u32::from_str_radix(crc32.as_str(), 16).ok()),
+        attributes: ReleaseAttributes::default(),
+        extension: caps.name("extension").map(|ext| ext.as_str().to_string()),
+    })
+}
+
+const SCENE_EXTENSIONS: &[&str] = &["mkv", "mp4", "avi", "webm"];
+
+// matches a token like "S01E02", or "S01E01E02" and "S01E01-E03" for multi-episode releases
+fn match_sxxexx(token: &str) -> Option<(i32, Vec<i32>)> {
+    let rest = token.strip_prefix(['S', 's'])?;
+    let (season, mut rest) = rest.split_once(['E', 'e'])?;
+    if season.is_empty() {
+        return None;
+    }
+    let mut episodes: Vec<i32> = Vec::new();
+    let mut through = false;
+    loop {
+        let end = rest.find(['E', 'e', '-']).unwrap_or(rest.len());
+        let episode: i32 = rest[..end].parse().ok()?;
+        match episodes.last() {
+            Some(&last) if episode <= last => return None,
+            Some(&last) if through => episodes.extend(last + 1..=episode),
+            _ => episodes.push(episode),
+        }
+        rest = &rest[end..];
+        if let Some(after) = rest.strip_prefix('-') {
+            rest = after.strip_prefix(['E', 'e']).unwrap_or(after);
+            through = true;
+        } else if let Some(after) = rest.strip_prefix(['E', 'e']) {
+            rest = after;
+            through = false;
+        } else {
+            break;
+        }
+    }
+    Some((season.parse().ok()?, episodes))
+}
+
+// matches releases without a leading "[group]", like "Show.Name.S01E02.1080p.WEB.H264-GROUP"
+// or "Show Name - 02 (1080p)", where the group, if there is one, is a trailing "-GROUP"
+fn match_scene_title(title: &str) -> Option<Episode> {
+    let title = title.trim();
+    let (title, extension) = match title.rsplit_once('.') {
+        Some((rest, ext)) if SCENE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) => {
+            (rest, Some(ext.to_string()))
+        }
+        _ => (title, None),
+    };
+    let (rest, group) = match title.rsplit_once('-') {
+        Some((rest, group))
+            if !group.is_empty()
+                && !group.contains([' ', '.', ')', ']'])
+                && !rest.ends_with(' ')
+                && !rest.to_uppercase().ends_with("WEB") =>
+        {
+            (rest, group.to_string())
+        }
+        _ => (title, String::new()),
+    };
+
+    // "Show Name - 02 (1080p)"
+    let bidx = rest.find(['(', '[']).unwrap_or(rest.len());
+    if let Some((name, episodes, kind, part, range, version)) =
+        match_name_ep_version(&rest[..bidx], " - ")
+    {
+        let tags = &rest[bidx..];
+        let quality = bracket_contents(tags)
+            .iter()
+            .flat_map(|content| content.split_whitespace())
+            .find_map(|word| Quality::from_str(word).ok());
+        return Some(Episode {
+            group,
+            name,
+            season: None,
+            episode: episodes[0],
+            episodes,
+            kind,
+            part,
+            range,
+            version,
+            quality,
+            crc32: match_crc32(tags),
+            attributes: match_attributes(tags),
+            extension,
+        });
+    }
+
+    // "Show.Name.S01E02.1080p.WEB.H264" or the same with spaces
+    let tokens: Vec<&str> = rest
+        .split(['.', ' ', '_'])
+        .filter(|token| !token.is_empty())
+        .collect();
+    let idx = tokens.iter().position(|token| match_sxxexx(token).is_some())?;
+    let (season, episodes) = match_sxxexx(tokens[idx])?;
+    let name = tokens[..idx].join(" ");
+    if name.is_empty() {
+        return None;
+    }
+    let mut attributes = ReleaseAttributes::default();
+    for token in &tokens[idx + 1..] {
+        match_attribute_word(&mut attributes, token);
+    }
+    Some(Episode {
+        group,
+        name,
+        season: Some(season),
+        episode: episodes[0],
+        episodes,
+        kind: EpisodeKind::Regular,
+        part: None,
+        range: None,
+        version: 1,
+        quality: tokens[idx + 1..]
+            .iter()
+            .find_map(|token| Quality::from_str(token).ok()),
+        crc32: None,
+        attributes,
+        extension,
+    })
+}
+
 pub fn match_title(title: &str) -> Option<Episode> {
+    match_title_with(title, GRAMMARS.get_or_init(builtin_grammars))
+}
+
+/// The step of `match_title` that gave up on a title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseStage {
+    Group,
+    Name,
+    Episode,
+    Version,
+    Quality,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParseFailure {
+    pub stage: ParseStage,
+    pub reason: String,
+}
+
+/// Like `match_title`, but explains where parsing stopped when the title doesn't match.
+pub fn parse_title(title: &str) -> Result<Episode, ParseFailure> {
+    let grammars = GRAMMARS.get_or_init(builtin_grammars);
+    match_title_with(title, grammars).ok_or_else(|| diagnose(title, grammars))
+}
+
+// walks the same steps as `match_title_with` to find the one that failed
+fn diagnose(title: &str, grammars: &[TitleGrammar]) -> ParseFailure {
+    let fail = |stage, reason: String| ParseFailure { stage, reason };
+    let has_sxxexx = |text: &str| text.split(['.', ' ', '_']).any(|t| match_sxxexx(t).is_some());
     if !title.starts_with('[') {
-        return None;
+        return match title.rsplit_once(" - ") {
+            Some((_, episode)) if !has_sxxexx(title) => fail(
+                ParseStage::Episode,
+                format!("couldn't read \"{}\" as an episode", episode.trim()),
+            ),
+            _ => fail(
+                ParseStage::Episode,
+                "no \"[group]\" prefix, \" - <episode>\" or SxxEyy".to_string(),
+            ),
+        };
+    }
+    let idx = match title.find(']') {
+        Some(idx) => idx,
+        None => return fail(ParseStage::Group, "no \"]\" closing the group".to_string()),
+    };
+    if title[idx + 1..].trim().is_empty() {
+        return fail(ParseStage::Name, "nothing after the group".to_string());
+    }
+    let group = &title[1..idx];
+    let builtin;
+    let grammar = match grammars.iter().find(|grammar| grammar.group == group) {
+        Some(grammar) => grammar,
+        None => {
+            builtin = TitleGrammar::builtin(group);
+            &builtin
+        }
+    };
+    let (sqc, eqc, _) = grammar.delimiters();
+    let qidx = match grammar.quality {
+        QualityLocation::First => title[idx + 1..].find(sqc),
+        QualityLocation::Last => title[idx + 1..].rfind(sqc),
+    }
+    .map(|qidx| qidx + idx + 1);
+    let text = &title[idx + 1..qidx.unwrap_or(title.len())];
+    if text.trim().is_empty() {
+        return fail(ParseStage::Name, format!("no name before the first \"{}\"", sqc));
+    }
+    if match_name_ep_version(text, &grammar.separator).is_none()
+        && match_name_sxxexx(text).is_none()
+    {
+        return match text.rsplit_once(grammar.separator.as_str()) {
+            Some((_, episode)) => fail(
+                ParseStage::Episode,
+                format!("couldn't read \"{}\" as an episode", episode.trim()),
+            ),
+            None if !has_sxxexx(text) => fail(
+                ParseStage::Episode,
+                format!(
+                    "no \"{}\" separator or SxxEyy in \"{}\"",
+                    grammar.separator,
+                    text.trim()
+                ),
+            ),
+            None => fail(ParseStage::Episode, "SxxEyy isn't at the end of the name".to_string()),
+        };
+    }
+    match qidx {
+        Some(qidx) if title[qidx + 1..].starts_with('v') => fail(
+            ParseStage::Version,
+            format!("couldn't read the version in \"{}\"", &title[qidx..]),
+        ),
+        _ => fail(ParseStage::Quality, format!("no \"{}\" closing the quality", eqc)),
+    }
+}
+
+/// The `parse` command's JSON for a title.
+pub fn parse_json(title: &str) -> Value {
+    match parse_title(title) {
+        Ok(ep) => json!({ "title": title, "episode": ep }),
+        Err(failure) => json!({ "title": title, "error": failure }),
+    }
+}
+
+#[derive(Deserialize)]
+struct CorpusEntry {
+    title: String,
+    expected: Option<Map<String, Value>>,
+}
+
+/// A corpus title that didn't parse as expected.
+#[derive(Debug)]
+pub struct CorpusMismatch {
+    pub line: usize,
+    pub title: String,
+    pub field: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+/// Replays a corpus of JSON lines like `{"title": "...", "expected": {"name": "...", "episode": 6}}`,
+/// comparing only the fields given, with nested ones written like "attributes.source". An
+/// `"expected": null` means the title must not match. Blank lines and lines starting with "#"
+/// are skipped. Returns how many titles were checked along with the mismatches.
+pub fn replay_corpus(text: &str) -> Result<(usize, Vec<CorpusMismatch>), serde_json::Error> {
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry: CorpusEntry = serde_json::from_str(line)?;
+        checked += 1;
+        let parsed = parse_json(&entry.title);
+        let mismatch = |field: &str, expected: Value, actual: Value| CorpusMismatch {
+            line: idx + 1,
+            title: entry.title.clone(),
+            field: field.to_string(),
+            expected,
+            actual,
+        };
+        match (entry.expected, parsed.get("episode")) {
+            (None, Some(episode)) => {
+                mismatches.push(mismatch("episode", Value::Null, episode.clone()));
+            }
+            (None, None) => {}
+            (Some(expected), None) => {
+                let error = parsed.get("error").cloned().unwrap_or(Value::Null);
+                mismatches.push(mismatch("episode", Value::Object(expected), error));
+            }
+            (Some(expected), Some(episode)) => {
+                for (field, expected) in expected {
+                    let pointer = format!("/{}", field.replace('.', "/"));
+                    let actual = episode.pointer(&pointer).cloned().unwrap_or(Value::Null);
+                    if actual != expected {
+                        mismatches.push(mismatch(&field, expected, actual));
+                    }
+                }
+            }
+        }
+    }
+    Ok((checked, mismatches))
+}
+
+pub fn match_title_with(title: &str, grammars: &[TitleGrammar]) -> Option<Episode> {
+    if !title.starts_with('[') {
+        return match_scene_title(title);
     }
     if let Some(idx) = title.find(']') {
         let group = title[1..idx].to_string();
-        let (sqc, eqc, eqc_char) = if &group == "SubsPlease" || &group == "PAS" {
-            ("(", ")", ')')
-        } else {
-            ("[", "]", ']')
+        let builtin;
+        let grammar = match grammars.iter().find(|grammar| grammar.group == group) {
+            Some(grammar) => grammar,
+            None => {
+                builtin = TitleGrammar::builtin(&group);
+                &builtin
+            }
         };
+        let crc32 = match_crc32(&title[idx + 1..]);
+        let attributes = match_attributes(&title[idx + 1..]);
+        if let Some(regex) = &grammar.regex {
+            if let Some(mut ep) = match_title_regex(title, group.clone(), regex) {
+                ep.crc32 = ep.crc32.or(crc32);
+                ep.attributes = attributes;
+                return Some(ep);
+            }
+        }
+        let (sqc, eqc, eqc_char) = grammar.delimiters();
+        let separator = grammar.separator.as_str();
 
-        if let Some(qidx) = title[idx + 1..].find(sqc) {
-            let qidx = qidx + idx + 1;
-            let (name, season, episode, version) = match match_name_ep_version(&title[idx + 1..qidx]) {
-                Some((name, episode, version)) => (name, None, episode, version),
-                None => match match_name_sxxexx(&title[idx + 1..qidx]) {
-                    Some((name, season, episode, version)) => (name, Some(season), episode, version),
-                    None => return None,
-                },
-            };
-            let (qidx, version) = if version == 1 && &title[qidx + 1..qidx + 2] == "v" {
+        let qidx = match grammar.quality {
+            QualityLocation::First => title[idx + 1..].find(sqc),
+            QualityLocation::Last => title[idx + 1..].rfind(sqc),
+        };
+        if let Some(qidx) = qidx {
+            let mut qidx = qidx + idx + 1;
+            let (name, season, episodes, kind, part, range, version) =
+                match match_name_ep_version(&title[idx + 1..qidx], separator) {
+                    Some((name, episodes, kind, part, range, version)) => {
+                        (name, None, episodes, kind, part, range, version)
+                    }
+                    None => match match_name_sxxexx(&title[idx + 1..qidx]) {
+                        Some((name, season, episodes, version)) => (
+                            name,
+                            Some(season),
+                            episodes,
+                            EpisodeKind::Regular,
+                            None,
+                            None,
+                            version,
+                        ),
+                        None if is_batch_title(title) => {
+                            let (name, season, range, ridx) =
+                                match_name_batch(title, idx + 1, qidx, sqc, eqc)?;
+                            qidx = ridx;
+                            let episodes = vec![range.first];
+                            let kind = EpisodeKind::Regular;
+                            (name, season, episodes, kind, None, Some(range), 1)
+                        }
+                        None => return None,
+                    },
+                };
+            let (qidx, version) = if version == 1 && title[qidx + 1..].starts_with('v') {
                 match title[qidx + 1..].find(eqc) {
-                    Some(veidx) => match title[qidx + 2..qidx + 1 + veidx].parse::<i32>() {
-                        Ok(v) => {
+                    Some(veidx) => match title.get(qidx + 2..qidx + 1 + veidx).map(str::parse::<i32>) {
+                        Some(Ok(v)) => {
                             if let Some(qsidx) = title[qidx + 2..].find(sqc) {
                                 (qidx + 2 + qsidx, v)
                             } else {
                                 return None;
                             }
                         }
-                        Err(_) => return None,
+                        _ => return None,
                     },
                     None => return None,
                 }
-            } else if title.len() < qidx + 5 {
-                (qidx, version)
-            } else if &title[qidx + 1..qidx + 5] == "VRV]" {
-                (qidx + 5, version)
-            } else if &title[qidx + 1..qidx + 5] == "WEB " {
-                (qidx + 4, version)
             } else {
-                (qidx, version)
+                (skip_tags(title, qidx, &grammar.skip, sqc, eqc_char), version)
             };
-            let quality = match title[qidx + 1..].find(|c| c == eqc_char || c == ' ') {
+            let quality = match title[qidx + 1..].find([eqc_char, ' ']) {
                 Some(qeidx) => Quality::from_str(&title[qidx + 1..qidx + 1 + qeidx]).ok(),
                 None => return None,
             };
@@ -125,38 +867,52 @@ pub fn match_title(title: &str) -> Option<Episode> {
                 group,
                 name,
                 season,
-                episode,
+                episode: episodes[0],
+                episodes,
+                kind,
+                part,
+                range,
                 version,
                 quality,
+                crc32,
+                attributes,
                 extension,
             })
         } else if let Some(ext_idx) = title[idx..].rfind('.') {
             let ext_idx = ext_idx + idx;
-            let (name, episode, version) = match match_name_ep_version(&title[idx..ext_idx]) {
-                Some((name, episode, version)) => (name, episode, version),
-                None => return None,
-            };
+            let (name, episodes, kind, part, range, version) =
+                match_name_ep_version(&title[idx..ext_idx], separator)?;
             Some(Episode {
                 group,
                 name,
                 season: None,
-                episode,
+                episode: episodes[0],
+                episodes,
+                kind,
+                part,
+                range,
                 version,
                 quality: None,
+                crc32,
+                attributes,
                 extension: Some(title[ext_idx + 1..].to_string()),
             })
         } else {
-            let (name, episode, version) = match match_name_ep_version(&title[idx + 1..]) {
-                Some((name, episode, version)) => (name, episode, version),
-                None => return None,
-            };
+            let (name, episodes, kind, part, range, version) =
+                match_name_ep_version(&title[idx + 1..], separator)?;
             Some(Episode {
                 group,
                 name,
                 season: None,
-                episode,
+                episode: episodes[0],
+                episodes,
+                kind,
+                part,
+                range,
                 version,
                 quality: None,
+                crc32,
+                attributes,
                 extension: None,
             })
         }
@@ -165,6 +921,33 @@ pub fn match_title(title: &str) -> Option<Episode> {
     }
 }
 
+// more episodes than any show has, so a typo like "1-2147483647" doesn't allocate gigabytes
+const MAX_EPISODE_RANGE: usize = 10_000;
+
+/// Parses episode selections like "5", "1-12" or "1,3,5-7", of at most `MAX_EPISODE_RANGE`
+/// episodes.
+pub fn parse_episode_range(text: &str) -> Option<Vec<i32>> {
+    let mut episodes = Vec::new();
+    for part in text.split(',').map(str::trim) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start = start.trim().parse::<i32>().ok()?;
+                let end = end.trim().parse::<i32>().ok()?;
+                let len = (end as i64 - start as i64 + 1) as usize;
+                if start > end || episodes.len() + len > MAX_EPISODE_RANGE {
+                    return None;
+                }
+                episodes.extend(start..=end);
+            }
+            None => episodes.push(part.parse::<i32>().ok()?),
+        }
+    }
+    if episodes.len() > MAX_EPISODE_RANGE {
+        return None;
+    }
+    Some(episodes)
+}
+
 #[cfg(test)]
 mod test {
 
@@ -259,6 +1042,7 @@ mod test {
         assert_eq!(ep.episode, 8);
         assert_eq!(ep.quality, Some(Quality::Mid_720p));
         assert_eq!(ep.version, 1);
+        assert_eq!(ep.crc32, Some(0xE2508E65));
 
         let ep = match_title("[PAS] Beastars S2 - 13 (WEB 1080 AAC) [8CF487D4].mkv")
             .expect("Failed to match 10th example");
@@ -270,17 +1054,16 @@ mod test {
         assert_eq!(ep.quality, Some(Quality::HD_1080p));
         assert_eq!(ep.version, 1);
 
-        // TODO: fix this
-        // let ep = match_title(
-        //     "[SubsPlease] Dragon Quest - Dai no Daibouken (2020) - 08 (720p) [2CB58E42].mkv",
-        // )
-        // .expect("Failed to match 11th example");
+        let ep = match_title(
+            "[SubsPlease] Dragon Quest - Dai no Daibouken (2020) - 08 (720p) [2CB58E42].mkv",
+        )
+        .expect("Failed to match 11th example");
 
-        // assert_eq!(ep.name, "Dragon Quest - Dai no Daibouken (2020)");
-        // assert_eq!(ep.group, "SubsPlease");
-        // assert_eq!(ep.episode, 8);
-        // assert_eq!(ep.quality, Some(Quality::Mid_720p));
-        // assert_eq!(ep.version, 1);
+        assert_eq!(ep.name, "Dragon Quest - Dai no Daibouken (2020)");
+        assert_eq!(ep.group, "SubsPlease");
+        assert_eq!(ep.episode, 8);
+        assert_eq!(ep.quality, Some(Quality::Mid_720p));
+        assert_eq!(ep.version, 1);
 
         let ep = match_title(
             "[Judas] Kimetsu no Yaiba - Katanakaji no Sato-hen (Demon Slayer - Swordsmith Village Arc) - S04E06 [1080p][HEVC x265 10bit][Multi-Subs] (Weekly)")
@@ -309,4 +1092,469 @@ mod test {
         assert_eq!(ep.quality, Some(Quality::HD_1080p));
         assert_eq!(ep.version, 1);
     }
+
+    #[test]
+    fn test_special_episodes() {
+        let ep = match_title("[SubsPlease] Tensei Shitara Slime Datta Ken - 24.5 (1080p) [A1B2C3D4].mkv")
+            .expect("failed to match fractional episode");
+
+        assert_eq!(ep.name, "Tensei Shitara Slime Datta Ken");
+        assert_eq!(ep.episode, 24);
+        assert_eq!(ep.part, Some(50));
+        assert_eq!(ep.kind, EpisodeKind::Special);
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+        assert_eq!(ep.label(), "24.5");
+
+        let ep = match_title("[SubsPlease] Tensei Shitara Slime Datta Ken - 24.05 (1080p).mkv")
+            .expect("failed to match fractional episode with two digits");
+        assert_eq!(ep.episode, 24);
+        assert_eq!(ep.part, Some(5));
+        assert_eq!(ep.label(), "24.05");
+        assert_eq!(ep.entry("", false).season_label(), "S01 24.05");
+
+        let ep = match_title("[Erai-raws] Kaguya-sama wa Kokurasetai - OVA [720p].mkv")
+            .expect("failed to match OVA");
+
+        assert_eq!(ep.name, "Kaguya-sama wa Kokurasetai");
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.kind, EpisodeKind::Ova);
+        assert_eq!(ep.part, None);
+        assert_eq!(ep.quality, Some(Quality::Mid_720p));
+
+        let ep = match_title("[Judas] Dorohedoro - SP02v2 [1080p][HEVC x265 10bit][Eng-Subs]")
+            .expect("failed to match special");
+
+        assert_eq!(ep.name, "Dorohedoro");
+        assert_eq!(ep.episode, 2);
+        assert_eq!(ep.kind, EpisodeKind::Special);
+        assert_eq!(ep.version, 2);
+
+        let ep = match_title("[Judas] Dorohedoro - NCOP1 [1080p][HEVC x265 10bit][Eng-Subs]")
+            .expect("failed to match creditless opening");
+
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.kind, EpisodeKind::CreditlessOp);
+
+        let ep = match_title("[Judas] Dorohedoro - Recap [1080p][HEVC x265 10bit][Eng-Subs]")
+            .expect("failed to match recap");
+
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.kind, EpisodeKind::Recap);
+
+        let ep = match_title("[Judas] Dorohedoro - 06 [1080p][HEVC x265 10bit][Eng-Subs].mkv")
+            .expect("failed to match regular episode");
+
+        assert_eq!(ep.kind, EpisodeKind::Regular);
+        assert_eq!(ep.part, None);
+
+        assert!(match_title("[Judas] Dorohedoro - Spring [1080p]").is_none());
+    }
+
+    #[test]
+    fn test_crc32() {
+        let ep = match_title("[Die Hot 14 - My Hot Will Go On (Director's Cut)] Great Pretender - 14v2 [57023320].mkv")
+            .expect("failed to match title with checksum");
+
+        assert_eq!(ep.crc32, Some(0x57023320));
+        assert_eq!(ep.quality, None);
+
+        let ep = match_title("[PAS] Beastars S2 - 13 (WEB 1080 AAC) [8CF487D4].mkv")
+            .expect("failed to match title with checksum");
+
+        assert_eq!(ep.crc32, Some(0x8CF487D4));
+
+        let ep = match_title("[Judas] Dorohedoro - 06 [1080p][HEVC x265 10bit][Eng-Subs].mkv")
+            .expect("failed to match title without checksum");
+
+        assert_eq!(ep.crc32, None);
+
+        let path = std::env::temp_dir().join(format!("tosho-crc32-{}", std::process::id()));
+        std::fs::write(&path, b"123456789").expect("failed to write test file");
+        let crc32 = file_crc32(&path);
+        std::fs::remove_file(&path).expect("failed to remove test file");
+        assert_eq!(crc32.expect("failed to hash test file"), 0xCBF43926);
+    }
+
+    #[test]
+    fn test_attributes() {
+        let ep = match_title("[Judas] Dorohedoro - 06 [1080p][HEVC x265 10bit][Eng-Subs].mkv")
+            .expect("failed to match Judas title");
+
+        assert_eq!(ep.attributes.video_codec.as_deref(), Some("HEVC"));
+        assert_eq!(ep.attributes.bit_depth, Some(10));
+        assert_eq!(ep.attributes.subtitles, vec!["Eng".to_string()]);
+        assert_eq!(ep.attributes.source, None);
+        assert_eq!(ep.attributes.tags(), vec!["hevc", "10bit", "eng-subs"]);
+
+        let ep = match_title("[PAS] Beastars S2 - 13 (WEB 1080 AAC) [8CF487D4].mkv")
+            .expect("failed to match PAS title");
+
+        assert_eq!(ep.attributes.source.as_deref(), Some("WEB"));
+        assert_eq!(ep.attributes.audio_codec.as_deref(), Some("AAC"));
+
+        let ep = match_title(
+            "[EMBER] Isekai Ojisan S01E01 [1080p] [HEVC WEBRip DDP] (Uncle from Another World)",
+        )
+        .expect("failed to match EMBER title");
+
+        assert_eq!(ep.attributes.video_codec.as_deref(), Some("HEVC"));
+        assert_eq!(ep.attributes.source.as_deref(), Some("WEB"));
+        assert_eq!(ep.attributes.audio_codec.as_deref(), Some("DDP"));
+
+        let ep = match_title("[Erai-raws] Dungeon ni Deai wo Motomeru no wa Machigatteiru Darou ka III - 05v2 [VRV][480p][Multiple Subtitle].mkv")
+            .expect("failed to match Erai-raws title");
+
+        assert_eq!(ep.attributes.subtitles, vec!["Multi".to_string()]);
+        assert!(!ep.attributes.raw);
+
+        let ep = match_title("[Group] Show - 03 [BD 1080p AVC FLAC][RAW][Uncensored]")
+            .expect("failed to match raw title");
+
+        assert_eq!(ep.attributes.video_codec.as_deref(), Some("AVC"));
+        assert_eq!(ep.attributes.source.as_deref(), Some("BD"));
+        assert_eq!(ep.attributes.audio_codec.as_deref(), Some("FLAC"));
+        assert!(ep.attributes.raw);
+        assert!(ep.attributes.uncensored);
+
+        let filter = crate::models::AttributeFilter {
+            require: crate::models::parse_tags("BD, flac"),
+            reject: crate::models::parse_tags("uncensored"),
+        };
+        assert!(!filter.allows(&ep.attributes));
+        let filter = crate::models::AttributeFilter {
+            require: crate::models::parse_tags("bd,flac"),
+            reject: Vec::new(),
+        };
+        assert!(filter.allows(&ep.attributes));
+    }
+
+    #[test]
+    fn test_group_grammars() {
+        let groups: HashMap<String, GroupGrammar> = toml::from_str(
+            r#"
+            [Foo]
+            brackets = "round"
+            quality = "last"
+            separator = " | "
+
+            [Bar]
+            regex = '^\[Bar\] (?P<name>.+?) E(?P<episode>\d+)(v(?P<version>\d+))? \[(?P<quality>\d+p)\]'
+            "#,
+        )
+        .expect("failed to parse grammars");
+        let grammars = groups
+            .iter()
+            .map(|(group, grammar)| TitleGrammar::new(group, grammar))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("failed to build grammars");
+
+        let ep = match_title_with("[Foo] Some Show (2021) | 03 (1080p) [ABCD1234].mkv", &grammars)
+            .expect("failed to match with custom separator");
+
+        assert_eq!(ep.name, "Some Show (2021)");
+        assert_eq!(ep.group, "Foo");
+        assert_eq!(ep.episode, 3);
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+        assert!(match_title("[Foo] Some Show (2021) | 03 (1080p) [ABCD1234].mkv").is_none());
+
+        let ep = match_title_with("[Bar] Other Show E05v2 [720p]", &grammars)
+            .expect("failed to match with regex");
+
+        assert_eq!(ep.name, "Other Show");
+        assert_eq!(ep.group, "Bar");
+        assert_eq!(ep.episode, 5);
+        assert_eq!(ep.version, 2);
+        assert_eq!(ep.quality, Some(Quality::Mid_720p));
+
+        // titles the regex doesn't cover fall back to the bracket rules
+        let ep = match_title_with("[Bar] Other Show - 06 [720p]", &grammars)
+            .expect("failed to fall back from regex");
+
+        assert_eq!(ep.name, "Other Show");
+        assert_eq!(ep.episode, 6);
+
+        let bad: HashMap<String, GroupGrammar> =
+            toml::from_str("[Baz]\nregex = '(?P<name>'").expect("failed to parse grammar");
+        assert!(TitleGrammar::new("Baz", &bad["Baz"]).is_err());
+    }
+
+    #[test]
+    fn test_group_grammar_overrides() {
+        let groups: HashMap<String, GroupGrammar> =
+            toml::from_str("[SubsPlease]\nseparator = \" ~ \"").expect("failed to parse grammar");
+        let grammars = vec![TitleGrammar::new("SubsPlease", &groups["SubsPlease"])
+            .expect("failed to build grammar")];
+
+        // the built-in round brackets and last-bracket quality still apply
+        let ep = match_title_with(
+            "[SubsPlease] Jujutsu Kaisen (2023) ~ 08 (1080p) [E2508E65].mkv",
+            &grammars,
+        )
+        .expect("failed to match with overridden separator");
+
+        assert_eq!(ep.name, "Jujutsu Kaisen (2023)");
+        assert_eq!(ep.group, "SubsPlease");
+        assert_eq!(ep.episode, 8);
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+    }
+
+    #[test]
+    fn test_batches() {
+        let ep = match_title("[Judas] Dorohedoro (Season 1) [1080p][Batch]")
+            .expect("failed to match season batch");
+
+        assert_eq!(ep.name, "Dorohedoro");
+        assert_eq!(ep.group, "Judas");
+        assert_eq!(ep.season, Some(1));
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.range, Some(EpisodeRange { first: 1, last: None }));
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+
+        let ep = match_title("[Group] Show - 01-12 [720p]").expect("failed to match range");
+
+        assert_eq!(ep.name, "Show");
+        assert_eq!(ep.season, None);
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.range, Some(EpisodeRange { first: 1, last: Some(12) }));
+        assert_eq!(ep.quality, Some(Quality::Mid_720p));
+        assert!(ep.range.unwrap().contains(12));
+        assert!(!ep.range.unwrap().contains(13));
+
+        let ep = match_title("[SubsPlease] Jujutsu Kaisen (01-24) (1080p) [Batch]")
+            .expect("failed to match bracketed range");
+
+        assert_eq!(ep.name, "Jujutsu Kaisen");
+        assert_eq!(ep.range, Some(EpisodeRange { first: 1, last: Some(24) }));
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+
+        let ep = match_title("[EMBER] Dr. Stone S03 [1080p] [Batch]")
+            .expect("failed to match sNN batch");
+
+        assert_eq!(ep.name, "Dr. Stone");
+        assert_eq!(ep.season, Some(3));
+        assert!(ep.is_batch());
+
+        let ep = match_title("[Judas] Dorohedoro - 06 [1080p][HEVC x265 10bit][Eng-Subs].mkv")
+            .expect("failed to match regular episode");
+
+        assert_eq!(ep.range, None);
+        assert!(match_title("[Judas] Dorohedoro (Season 1) [1080p]").is_none());
+    }
+
+    #[test]
+    fn test_qualities() {
+        assert_eq!(Quality::from_str("2160p").ok(), Some(Quality::UHD_2160p));
+        assert_eq!(Quality::from_str("4K").ok(), Some(Quality::UHD_2160p));
+        assert_eq!(Quality::from_str("576p").ok(), Some(Quality::Low_576p));
+        assert_eq!(Quality::from_str("1920x1080").ok(), Some(Quality::HD_1080p));
+        assert_eq!(Quality::from_str("1280x720").ok(), Some(Quality::Mid_720p));
+        assert_eq!(Quality::from_str("960x540").ok(), Some(Quality::Low_540p));
+        assert_eq!(Quality::from_str("1920x1079").ok(), None);
+        assert_eq!(Quality::from_str("x1080").ok(), None);
+
+        // stored qualities have to keep reading back the same
+        for quality in ["480p", "540p", "576p", "720p", "1080p", "2160p"] {
+            assert_eq!(Quality::from_str(quality).unwrap().to_string(), quality);
+        }
+
+        assert!(Quality::Low_480p < Quality::Low_576p);
+        assert!(Quality::Mid_720p < Quality::HD_1080p);
+        assert!(Quality::HD_1080p < Quality::UHD_2160p);
+
+        let ep = match_title("[Group] Show - 04 [1920x1080 HEVC].mkv")
+            .expect("failed to match explicit resolution");
+
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+
+        let ep = match_title("[Group] Show - 04 [2160p][HEVC 10bit].mkv")
+            .expect("failed to match 4K title");
+
+        assert_eq!(ep.quality, Some(Quality::UHD_2160p));
+    }
+
+    #[test]
+    fn test_scene_titles() {
+        let ep = match_title("Show.Name.S01E02.1080p.WEB.H264-GROUP")
+            .expect("failed to match dotted scene title");
+
+        assert_eq!(ep.name, "Show Name");
+        assert_eq!(ep.group, "GROUP");
+        assert_eq!(ep.season, Some(1));
+        assert_eq!(ep.episode, 2);
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+        assert_eq!(ep.version, 1);
+        assert_eq!(ep.attributes.source.as_deref(), Some("WEB"));
+        assert_eq!(ep.attributes.video_codec.as_deref(), Some("AVC"));
+        assert_eq!(ep.extension, None);
+
+        let ep = match_title("Kimi.no.Na.wa.S02E11.720p.WEB-DL.DDP5.1.H.264-NTb.mkv")
+            .expect("failed to match scene title with extension");
+
+        assert_eq!(ep.name, "Kimi no Na wa");
+        assert_eq!(ep.group, "NTb");
+        assert_eq!(ep.season, Some(2));
+        assert_eq!(ep.episode, 11);
+        assert_eq!(ep.quality, Some(Quality::Mid_720p));
+        assert_eq!(ep.attributes.audio_codec.as_deref(), Some("DDP"));
+        assert_eq!(ep.extension.as_deref(), Some("mkv"));
+
+        let ep = match_title("Show Name - 02 (1080p)").expect("failed to match untagged title");
+
+        assert_eq!(ep.name, "Show Name");
+        assert_eq!(ep.group, "");
+        assert_eq!(ep.season, None);
+        assert_eq!(ep.episode, 2);
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+
+        let ep = match_title("Show Name S03E04 2160p WEB HEVC-GROUP")
+            .expect("failed to match spaced scene title");
+
+        assert_eq!(ep.name, "Show Name");
+        assert_eq!(ep.group, "GROUP");
+        assert_eq!(ep.season, Some(3));
+        assert_eq!(ep.episode, 4);
+        assert_eq!(ep.quality, Some(Quality::UHD_2160p));
+
+        assert!(match_title("Show.Name.1080p.WEB.H264-GROUP").is_none());
+        assert!(match_title("Just some words").is_none());
+    }
+
+    #[test]
+    fn test_season_markers() {
+        let season2 = Some(("Beastars".to_string(), 2));
+        assert_eq!(normalize_season("Beastars S2"), season2);
+        assert_eq!(normalize_season("Beastars Season 2"), season2);
+        assert_eq!(normalize_season("Beastars (Season 2)"), season2);
+        assert_eq!(normalize_season("Beastars 2nd Season"), season2);
+        assert_eq!(normalize_season("Beastars II"), season2);
+        assert_eq!(
+            normalize_season("Dungeon ni Deai wo Motomeru no wa Machigatteiru Darou ka III"),
+            Some((
+                "Dungeon ni Deai wo Motomeru no wa Machigatteiru Darou ka".to_string(),
+                3
+            ))
+        );
+        assert_eq!(normalize_season("Beastars"), None);
+        assert_eq!(normalize_season("Dr. Stone"), None);
+        assert_eq!(normalize_season("Dragon Quest - Dai no Daibouken (2020)"), None);
+        assert_eq!(normalize_season("S2"), None);
+
+        // the parser itself leaves the name alone, shows opt in to merging seasons
+        let ep = match_title("[PAS] Beastars S2 - 13 (WEB 1080 AAC) [8CF487D4].mkv")
+            .expect("failed to match season marker title");
+
+        assert_eq!(ep.name, "Beastars S2");
+        assert_eq!(ep.season, None);
+    }
+
+    #[test]
+    fn test_parse_failures() {
+        let stage = |title: &str| parse_title(title).err().map(|failure| failure.stage);
+
+        assert_eq!(stage("[Judas Dorohedoro - 06.mkv"), Some(ParseStage::Group));
+        assert_eq!(stage("[Judas]"), Some(ParseStage::Name));
+        assert_eq!(stage("[Judas] [1080p]"), Some(ParseStage::Name));
+        assert_eq!(stage("[Judas] Dorohedoro - Spring [1080p]"), Some(ParseStage::Episode));
+        assert_eq!(stage("[Judas] Dorohedoro [1080p]"), Some(ParseStage::Episode));
+        assert_eq!(stage("[Judas] Dorohedoro - 06 [v2"), Some(ParseStage::Version));
+        assert_eq!(stage("Just some words"), Some(ParseStage::Episode));
+        assert_eq!(stage("[Judas] Dorohedoro - 06 [1080p].mkv"), None);
+        // these used to panic slicing past the end of the title or through a multibyte char
+        assert_eq!(stage("[Judas] Show - 01 ["), Some(ParseStage::Quality));
+        assert_eq!(stage("[Judas] Show - 01 [v]"), Some(ParseStage::Version));
+        let ep = parse_title("[Judas] Show - 01 [日本]").expect("failed to parse non-ascii tag");
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.quality, None);
+        let ep = parse_title("[Grp] Show - 01 [1080p][ﬁ-subs].mkv")
+            .expect("failed to parse non-ascii subtitle tag");
+        assert_eq!(ep.attributes.subtitles, vec!["ﬁ".to_string()]);
+
+        let json = parse_json("[SubsPlease] Jujutsu Kaisen - 08 (720p) [E2508E65].mkv");
+        assert_eq!(json["episode"]["name"], "Jujutsu Kaisen");
+        assert_eq!(json["episode"]["quality"], "720p");
+        assert_eq!(json["episode"]["kind"], "regular");
+        assert_eq!(json["episode"]["crc32"], "E2508E65");
+
+        let json = parse_json("[Judas] Dorohedoro [1080p]");
+        assert_eq!(json["error"]["stage"], "episode");
+    }
+
+    #[test]
+    fn test_corpus() {
+        let (checked, mismatches) =
+            replay_corpus(include_str!("../corpus/titles.jsonl")).expect("failed to read corpus");
+
+        assert!(checked > 0);
+        assert!(mismatches.is_empty(), "{:#?}", mismatches);
+
+        let (checked, mismatches) = replay_corpus(
+            r#"# comments and blank lines are skipped
+
+            {"title": "[Judas] Dorohedoro - 06 [1080p].mkv", "expected": {"episode": 7, "attributes.raw": false}}
+            {"title": "[Judas] Dorohedoro [1080p]", "expected": null}"#,
+        )
+        .expect("failed to read inline corpus");
+
+        assert_eq!(checked, 2);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].line, 3);
+        assert_eq!(mismatches[0].field, "episode");
+        assert_eq!(mismatches[0].actual, json!(6));
+    }
+
+    #[test]
+    fn test_episode_ranges() {
+        assert_eq!(parse_episode_range("5"), Some(vec![5]));
+        assert_eq!(parse_episode_range("1-3"), Some(vec![1, 2, 3]));
+        assert_eq!(parse_episode_range("1, 3,5-7"), Some(vec![1, 3, 5, 6, 7]));
+        assert_eq!(parse_episode_range("3-1"), None);
+        assert_eq!(parse_episode_range("1-10000").map(|eps| eps.len()), Some(10000));
+        assert_eq!(parse_episode_range("1-2147483647"), None);
+        assert_eq!(parse_episode_range("1-10000,10001"), None);
+        assert_eq!(parse_episode_range("one"), None);
+        assert_eq!(parse_episode_range(""), None);
+    }
+
+    #[test]
+    fn test_multi_episodes() {
+        let ep = match_title("[EMBER] Isekai Ojisan S01E01E02 [1080p] [HEVC WEBRip DDP]")
+            .expect("failed to match S01E01E02");
+
+        assert_eq!(ep.name, "Isekai Ojisan");
+        assert_eq!(ep.season, Some(1));
+        assert_eq!(ep.episode, 1);
+        assert_eq!(ep.episodes, vec![1, 2]);
+        assert_eq!(ep.label(), "01+02");
+
+        let entries = ep.entries("https://example.com/a.nzb", false);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].episode, 2);
+        assert!(entries.iter().all(|e| e.link == "https://example.com/a.nzb"));
+
+        let ep = match_title("[SubsPlease] Jujutsu Kaisen - 01+02 (1080p) [E2508E65].mkv")
+            .expect("failed to match 01+02");
+
+        assert_eq!(ep.name, "Jujutsu Kaisen");
+        assert_eq!(ep.episodes, vec![1, 2]);
+        assert_eq!(ep.quality, Some(Quality::HD_1080p));
+
+        let ep = match_title("[Judas] Dorohedoro - 11 & 12 [1080p][HEVC x265 10bit][Eng-Subs].mkv")
+            .expect("failed to match 11 & 12");
+
+        assert_eq!(ep.name, "Dorohedoro");
+        assert_eq!(ep.episodes, vec![11, 12]);
+        assert!(!ep.is_batch());
+
+        let ep = match_title("Show.Name.S02E03-E05.1080p.WEB.H264-GROUP")
+            .expect("failed to match S02E03-E05");
+
+        assert_eq!(ep.season, Some(2));
+        assert_eq!(ep.episodes, vec![3, 4, 5]);
+
+        let ep = match_title("[SubsPlease] Jujutsu Kaisen - 08 (720p) [E2508E65].mkv").unwrap();
+        assert_eq!(ep.episodes, vec![8]);
+        assert!(!ep.is_multi_episode());
+
+        assert!(match_title("[SubsPlease] Jujutsu Kaisen - 02+01 (1080p).mkv").is_none());
+    }
 }